@@ -9,15 +9,26 @@ fn main() {
       let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
       let libfap_dir = "src/libfap-1.5";
  
-      cc::Build::new()
-            .include(format!("{}/src", libfap_dir))
+      let mut build = cc::Build::new();
+      build.include(format!("{}/src", libfap_dir))
             .files([
                   format!("{}/src/fap.c", libfap_dir),
                   format!("{}/src/helpers.c", libfap_dir),
                   format!("{}/src/helpers2.c", libfap_dir),
             ].iter())
-            .warnings(false)
-            .compile("libfap");
+            .warnings(false);
+
+      // cargo-fuzz and other sanitizer builds only instrument Rust code, do the same for libfap
+      if let Ok(sanitizers) = env::var("CARGO_CFG_SANITIZE") {
+            for sanitizer in sanitizers.split(',') {
+                  build.flag(format!("-fsanitize={}", sanitizer));
+            }
+            build.flag("-fno-omit-frame-pointer");
+      }
+      if env::var("CARGO_CFG_FUZZING").is_ok() {
+            build.flag_if_supported("-fsanitize=fuzzer-no-link");
+      }
+      build.compile("libfap");
 
       // let libfap_install_dir = autotools::build(libfap_dir);
       
@@ -30,9 +41,17 @@ fn main() {
         .header(format!("{}/src/fap.h", libfap_dir))
         .whitelist_type("fap_packet_t")
         .whitelist_function("fap_init")
+        .whitelist_function("fap_cleanup")
         .whitelist_function("fap_free")
         .whitelist_function("fap_explain_error")
         .whitelist_function("fap_parseaprs")
+        .whitelist_function("fap_parseaprs_at")
+        .whitelist_function("fap_distance")
+        .whitelist_function("fap_count_digihops")
+        .whitelist_function("fap_kiss_to_tnc2")
+        .whitelist_function("fap_tnc2_to_kiss")
+        .whitelist_function("fap_ax25_to_tnc2")
+        .whitelist_function("fap_tnc2_to_ax25")
         .generate()
         .expect("Unable to generate bindings")
         .write_to_file(out_dir.join("bindings.rs"))
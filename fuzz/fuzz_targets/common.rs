@@ -0,0 +1,31 @@
+use aprs::Packet as AprsPacket;
+use std::fmt::Write;
+
+/// Reads every field libfap may have filled in, so the sanitizers see all the pointers
+/// the wrapper follows.
+pub fn touch(packet: &fap::Packet) {
+    let mut out = String::new();
+    let _ = write!(out, "{} {} {}", packet, packet.header(), packet.body());
+    let _ = write!(out, "{:?} {:?} {:?}", packet.packet_type(), packet.format(), packet.orig_packet());
+    let _ = write!(out, "{:?} {:?}", packet.path(), packet.digipeaters());
+    let _ = write!(out, "{:?} {:?} {:?} {:?}", packet.pos_ambiguity(), packet.dao_datum_byte(),
+                   packet.symbol_table(), packet.symbol_code());
+    let _ = write!(out, "{:?} {:?} {:?} {:?} {:?} {:?}", packet.messaging(), packet.message_destination(),
+                   packet.message(), packet.message_ack(), packet.message_nack(), packet.message_id());
+    let _ = write!(out, "{:?} {:?} {:?} {:?} {:?}", packet.object_or_item_name(), packet.alive(),
+                   packet.gps_fix_status(), packet.radio_range(), packet.phg());
+    let _ = write!(out, "{:?} {:?} {:?} {:?}", packet.raw_timestamp(), packet.nmea_checksum_ok(),
+                   packet.wx_report(), packet.telemetry());
+    let _ = write!(out, "{:?} {:?} {:?}", packet.messagebits(), packet.status(), packet.capabilities());
+
+    let _ = write!(out, "{} {:?} {:?} {:?} {:?}", packet.source(), packet.symbol(), packet.timestamp(),
+                   packet.destination(), packet.comment());
+    let _ = write!(out, "{:?} {:?} {:?} {:?}", packet.latitude(), packet.longitude(), packet.precision(),
+                   packet.position());
+    let _ = write!(out, "{:?} {:?} {:?} {:?} {:?} {:?}", packet.speed(), packet.course(), packet.altitude(),
+                   packet.temperature(), packet.wind_direction(), packet.wind_speed());
+
+    let owned = packet.to_owned();
+    let _ = write!(out, "{} {} {:?}", owned, owned.path(), owned.object_or_item_name());
+    assert_eq!(owned, owned.clone());
+}
@@ -0,0 +1,22 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The first byte picks the conversion, the rest is the frame. Whatever converts is
+// converted back and parsed.
+fuzz_target!(|data: &[u8]| {
+    let (kind, frame) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let tnc2 = match kind % 4 {
+        0 => fap::kiss_to_tnc2(frame).map(|(tnc2, _)| tnc2),
+        1 => fap::ax25_to_tnc2(frame),
+        2 => fap::tnc2_to_kiss(frame, *kind >> 2)
+            .and_then(|kiss| fap::kiss_to_tnc2(&kiss).map(|(tnc2, _)| tnc2)),
+        _ => fap::tnc2_to_ax25(frame).and_then(|ax25| fap::ax25_to_tnc2(&ax25)),
+    };
+    if let Ok(tnc2) = tnc2 {
+        let _ = fap::tnc2_to_ax25(&tnc2);
+        let _ = fap::Packet::new(tnc2);
+    }
+});
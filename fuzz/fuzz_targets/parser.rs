@@ -0,0 +1,36 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use std::sync::OnceLock;
+
+mod common;
+
+// One parser for every option combination, built once so that libfap stays
+// initialized between inputs
+fn parsers() -> &'static [fap::Parser] {
+    static PARSERS: OnceLock<Vec<fap::Parser>> = OnceLock::new();
+    PARSERS.get_or_init(|| {
+        (0..8u8).map(|options| {
+            let comment_mode = if options & 4 != 0 {
+                fap::CommentMode::PerlCompatible
+            } else {
+                fap::CommentMode::Raw
+            };
+            fap::Parser::builder()
+                .ax25(options & 1 != 0)
+                .keep_original(options & 2 != 0)
+                .comment_mode(comment_mode)
+                .build()
+        }).collect()
+    })
+}
+
+// The first byte picks the parser options, the rest is the packet
+fuzz_target!(|data: &[u8]| {
+    let (options, raw) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    if let Ok(packet) = parsers()[(options & 7) as usize].parse(raw) {
+        common::touch(&packet);
+    }
+});
@@ -0,0 +1,306 @@
+use aprs::Packet as AprsPacket;
+use bind::fap_distance;
+use super::QConstruct;
+use std::fmt;
+use std::str::FromStr;
+use {opt_val, Error, Packet, PacketType};
+
+/// What a filter needs to know beyond the packet itself.
+///
+/// The `m/` and `f/` filters, `t/` with a range and `q//I` depend on other packets
+/// the server has seen. Without this information, as with `Filter::matches()`, they
+/// match nothing.
+pub trait FilterContext {
+    /// Last known position of the logged in client, for `m/`.
+    fn my_position(&self) -> Option<(f64, f64)> {
+        None
+    }
+
+    /// Last known position of a station, for `f/` and `t/` with a range.
+    fn position_of(&self, callsign: &str) -> Option<(f64, f64)> {
+        let _ = callsign;
+        None
+    }
+
+    /// Whether the station has been seen gating packets with `qAr`, `qAo` or `qAR`,
+    /// for `q//I`.
+    fn is_igate(&self, callsign: &str) -> bool {
+        let _ = callsign;
+        false
+    }
+}
+
+struct NoContext;
+
+impl FilterContext for NoContext {}
+
+/// APRS-IS server-side filter, as given with the login or the `#filter` command.
+///
+/// A filter is a list of space separated parts. A packet passes if any of the parts
+/// matches it and none of the parts starting with `-` does:
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::Packet;
+/// use fap::aprsis::Filter;
+///
+/// let filter: Filter = "r/49/-72/50 t/m -p/N0".parse().unwrap();
+/// assert!(filter.matches(&Packet::new("N1CALL>APRS:!4903.50N/07201.75W-").unwrap()));
+/// assert!(filter.matches(&Packet::new("N1CALL>APRS::N2CALL   :Hello").unwrap()));
+/// assert!(!filter.matches(&Packet::new("N0CALL>APRS:!4903.50N/07201.75W-").unwrap()));
+/// assert!(!filter.matches(&Packet::new("N1CALL>APRS:!6010.00N/02450.00E-").unwrap()));
+/// ```
+///
+/// Callsigns and names are compared ignoring case, and may end in a `*` wildcard.
+/// Distances are in km.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    include: Vec<Part>,
+    exclude: Vec<Part>,
+    text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    /// `r/lat/lon/dist`
+    Range(f64, f64, f64),
+    /// `m/dist`
+    MyRange(f64),
+    /// `f/call/dist`
+    FriendRange(String, f64),
+    /// `a/latN/lonW/latS/lonE`
+    Area(f64, f64, f64, f64),
+    /// `p/aa/bb/cc`
+    Prefix(Vec<String>),
+    /// `b/call1/call2`
+    Budlist(Vec<String>),
+    /// `o/obj1/obj2`
+    Object(Vec<String>),
+    /// `t/poimqstunw` or `t/poimqstunw/call/dist`
+    Type(String, Option<(String, f64)>),
+    /// `s/pri/alt/over`
+    Symbol(String, String, String),
+    /// `d/digi1/digi2`
+    Digi(Vec<String>),
+    /// `e/call1/call2`
+    Entry(Vec<String>),
+    /// `g/call1/call2`
+    Group(Vec<String>),
+    /// `u/unproto1/unproto2`
+    Unproto(Vec<String>),
+    /// `q/con/I`
+    QConstruct(String, bool),
+}
+
+impl Filter {
+    pub fn parse(filter: &str) -> Result<Filter, Error> {
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        for part in filter.split_whitespace() {
+            match part.strip_prefix('-') {
+                Some(part) => exclude.push(Part::parse(part)?),
+                None => include.push(Part::parse(part)?),
+            }
+        }
+        Ok(Filter { include, exclude, text: filter.split_whitespace().collect::<Vec<_>>().join(" ") })
+    }
+
+    /// Whether the packet passes the filter, for filters that only depend on the
+    /// packet itself.
+    pub fn matches(&self, packet: &Packet) -> bool {
+        self.matches_with(packet, &NoContext)
+    }
+
+    /// Whether the packet passes the filter, looking up positions and IGates in
+    /// `context`.
+    pub fn matches_with(&self, packet: &Packet, context: &dyn FilterContext) -> bool {
+        self.include.iter().any(|part| part.matches(packet, context))
+            && !self.exclude.iter().any(|part| part.matches(packet, context))
+    }
+}
+
+impl FromStr for Filter {
+    type Err = Error;
+
+    fn from_str(filter: &str) -> Result<Filter, Error> {
+        Filter::parse(filter)
+    }
+}
+
+/// The filter as sent to the server.
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+fn invalid<T>(part: &str) -> Result<T, Error> {
+    Err(Error::Other(format!("invalid filter {:?}", part)))
+}
+
+impl Part {
+    fn parse(part: &str) -> Result<Part, Error> {
+        let mut fields = part.split('/');
+        let kind = fields.next().unwrap_or("");
+        let args: Vec<&str> = fields.collect();
+        let number = |i: usize| -> Result<f64, Error> {
+            match args.get(i).and_then(|a| a.parse::<f64>().ok()) {
+                Some(n) if n.is_finite() => Ok(n),
+                _ => invalid(part),
+            }
+        };
+        let calls = || -> Result<Vec<String>, Error> {
+            if args.is_empty() || args.iter().any(|a| a.is_empty()) {
+                return invalid(part)
+            }
+            Ok(args.iter().map(|a| a.to_ascii_uppercase()).collect())
+        };
+        let parsed = match kind {
+            "r" if args.len() == 3 => Part::Range(number(0)?, number(1)?, number(2)?),
+            "m" if args.len() == 1 => Part::MyRange(number(0)?),
+            "f" if args.len() == 2 && !args[0].is_empty() => Part::FriendRange(args[0].to_ascii_uppercase(), number(1)?),
+            "a" if args.len() == 4 => Part::Area(number(0)?, number(1)?, number(2)?, number(3)?),
+            "p" => Part::Prefix(calls()?),
+            "b" => Part::Budlist(calls()?),
+            "o" => Part::Object(calls()?),
+            "t" if args.len() == 1 || args.len() == 3 => {
+                if args[0].is_empty() || !args[0].chars().all(|c| "poimqstunw".contains(c.to_ascii_lowercase())) {
+                    return invalid(part)
+                }
+                let range = match args.len() {
+                    3 if !args[1].is_empty() => Some((args[1].to_ascii_uppercase(), number(2)?)),
+                    3 => return invalid(part),
+                    _ => None,
+                };
+                Part::Type(args[0].to_ascii_lowercase(), range)
+            },
+            "s" if !args.is_empty() && args.len() <= 3 && args.iter().any(|a| !a.is_empty()) => {
+                let arg = |i: usize| args.get(i).map_or(String::new(), |a| a.to_string());
+                Part::Symbol(arg(0), arg(1), arg(2))
+            },
+            "d" => Part::Digi(calls()?),
+            "e" => Part::Entry(calls()?),
+            "g" => Part::Group(calls()?),
+            "u" => Part::Unproto(calls()?),
+            // q constructs are case sensitive
+            "q" if !args.is_empty() && args.len() <= 2 && (args.len() == 1 || args[1] == "I") => {
+                Part::QConstruct(args[0].to_string(), args.len() == 2)
+            },
+            _ => return invalid(part),
+        };
+        Ok(parsed)
+    }
+
+    fn matches(&self, packet: &Packet, context: &dyn FilterContext) -> bool {
+        match *self {
+            Part::Range(lat, lon, dist) => within(packet, (lat, lon), dist),
+            Part::MyRange(dist) => context.my_position().is_some_and(|center| within(packet, center, dist)),
+            Part::FriendRange(ref call, dist) => {
+                context.position_of(call).is_some_and(|center| within(packet, center, dist))
+            },
+            Part::Area(north, west, south, east) => match position(packet) {
+                Some((lat, lon)) => lat <= north && lat >= south && lon >= west && lon <= east,
+                None => false,
+            },
+            Part::Prefix(ref prefixes) => {
+                let source = packet.source().to_ascii_uppercase();
+                prefixes.iter().any(|p| source.starts_with(p.as_str()))
+            },
+            Part::Budlist(ref calls) => any_match(calls, &packet.source()),
+            Part::Object(ref names) => packet.object_or_item_name().is_some_and(|name| any_match(names, name.trim_end())),
+            Part::Type(ref types, ref range) => {
+                types.chars().any(|t| is_type(packet, t)) && match *range {
+                    Some((ref call, dist)) => context.position_of(call).is_some_and(|center| within(packet, center, dist)),
+                    None => true,
+                }
+            },
+            Part::Symbol(ref primary, ref alternate, ref overlays) => {
+                match (packet.symbol_table(), packet.symbol_code()) {
+                    (Some('/'), Some(code)) => primary.contains(code),
+                    (Some(table), Some(code)) => {
+                        alternate.contains(code) && (overlays.is_empty() || overlays.contains(table))
+                    },
+                    _ => false,
+                }
+            },
+            Part::Digi(ref calls) => {
+                let path = packet.path();
+                let path = &path[..q_construct(packet).map_or(path.len(), |(i, _)| i)];
+                // every element up to the last one marked as used has been digipeated
+                let used = path.iter().rposition(|p| p.ends_with('*')).map_or(0, |i| i + 1);
+                path[..used].iter().any(|p| any_match(calls, p.trim_end_matches('*')))
+            },
+            Part::Entry(ref calls) => {
+                let path = packet.path();
+                match q_construct(packet) {
+                    Some((i, _)) => path.get(i + 1).is_some_and(|entry| any_match(calls, entry)),
+                    None => false,
+                }
+            },
+            Part::Group(ref calls) => match packet.packet_type() {
+                Some(PacketType::Message) | Some(PacketType::TelemetryMessage) => {
+                    packet.message_destination().is_some_and(|to| any_match(calls, to.trim_end()))
+                },
+                _ => false,
+            },
+            Part::Unproto(ref calls) => packet.destination().is_some_and(|to| any_match(calls, &to)),
+            Part::QConstruct(ref constructs, igates) => {
+                let q = q_construct(packet).map(|(_, q)| q);
+                q.is_some_and(|q| constructs.contains(q))
+                    || (igates && is_type(packet, 'p') && context.is_igate(&packet.source()))
+            },
+        }
+    }
+}
+
+// Position of the packet, or of the object or item it reports
+fn position(packet: &Packet) -> Option<(f64, f64)> {
+    let fap = packet.fap();
+    unsafe { Some((opt_val(fap.latitude)?, opt_val(fap.longitude)?)) }
+}
+
+fn within(packet: &Packet, (lat, lon): (f64, f64), dist: f64) -> bool {
+    match position(packet) {
+        Some((plat, plon)) => {
+            let distance = unsafe { fap_distance(lon, lat, plon, plat) };
+            distance <= dist
+        },
+        None => false,
+    }
+}
+
+// `t/` letters: position, object, item, message, query, status, telemetry,
+// user-defined, NWS and weather
+fn is_type(packet: &Packet, t: char) -> bool {
+    let packet_type = packet.packet_type();
+    let is_message = matches!(packet_type, Some(PacketType::Message) | Some(PacketType::TelemetryMessage));
+    match t {
+        'p' => matches!(packet_type, Some(PacketType::Location) | Some(PacketType::MicE) | Some(PacketType::Nmea)),
+        'o' => packet_type == Some(PacketType::Object),
+        'i' => packet_type == Some(PacketType::Item),
+        'm' => is_message,
+        'q' => packet.body().starts_with('?'),
+        's' => packet_type == Some(PacketType::Status),
+        't' => packet_type == Some(PacketType::Telemetry),
+        'u' => packet.body().starts_with('{'),
+        'n' => is_message && packet.message_destination().is_some_and(|to| {
+            ["NWS-", "SKY", "CWA", "BOM"].iter().any(|p| to.starts_with(p))
+        }),
+        'w' => packet_type == Some(PacketType::Wx) || packet.wx_report().is_some(),
+        _ => false,
+    }
+}
+
+// Index and letter of the q construct in the path
+fn q_construct(packet: &Packet) -> Option<(usize, char)> {
+    packet.path().iter().enumerate().find_map(|(i, p)| QConstruct::parse(p).map(|q| (i, q.letter())))
+}
+
+// Calls and names in filters are uppercase, and may end in `*`
+fn any_match(patterns: &[String], call: &str) -> bool {
+    let call = call.to_ascii_uppercase();
+    patterns.iter().any(|p| match p.strip_suffix('*') {
+        Some(prefix) => call.starts_with(prefix),
+        None => call == *p,
+    })
+}
@@ -0,0 +1,320 @@
+//! APRS-IS client.
+//!
+//! `Client` logs in to an APRS-IS server and yields the packets it receives, one for
+//! each line. Server comments, including the keepalives sent every 20 seconds or so,
+//! are not passed on. Lost connections are reported as `Error::Io` and made again on
+//! the next call, after a delay that doubles with every failure:
+//!
+//! ```rust,no_run
+//! extern crate aprs;
+//! extern crate fap;
+//! use aprs::Packet;
+//! use fap::aprsis::Client;
+//!
+//! let client = Client::builder("N0CALL")
+//!     .server("rotate.aprs2.net:14580")
+//!     .filter("r/60.2/24.9/50")
+//!     .build();
+//! for packet in client {
+//!     match packet {
+//!         Ok(packet) => println!("{}: {}", packet.source(), packet.body()),
+//!         Err(err) => eprintln!("{}", err),
+//!     }
+//! }
+//! ```
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+use {Error, Packet, Parser};
+
+mod filter;
+mod qconstruct;
+
+pub use self::filter::{Filter, FilterContext};
+pub use self::qconstruct::{q_algorithm, Entry, QConstruct};
+
+/// APRS-IS passcode of a callsign, a 15 bit hash of the callsign without SSID.
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::aprsis::{passcode, verify_passcode};
+///
+/// assert_eq!(passcode("N0CALL-10"), 13023);
+/// assert!(verify_passcode("n0call", 13023));
+/// assert!(!verify_passcode("N0CALL", -1));
+/// ```
+pub fn passcode(callsign: &str) -> u16 {
+    let call = callsign.split('-').next().unwrap_or("").to_ascii_uppercase();
+    let mut hash: u16 = 0x73e2;
+    for pair in call.as_bytes().chunks(2) {
+        hash ^= u16::from(pair[0]) << 8;
+        if let Some(&low) = pair.get(1) {
+            hash ^= u16::from(low);
+        }
+    }
+    hash & 0x7fff
+}
+
+/// Whether `passcode` is the one of `callsign`, as a server checks a login. Negative
+/// passcodes, like the -1 of receive-only logins, are never valid.
+pub fn verify_passcode(callsign: &str, passcode: i32) -> bool {
+    passcode >= 0 && passcode == i32::from(self::passcode(callsign))
+}
+
+/// The `# logresp` line a server answers the login with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginResponse {
+    pub callsign: String,
+    /// Whether the passcode was accepted, so that the client may send packets
+    pub verified: bool,
+    /// Name of the server, like `T2FINLAND`
+    pub server: Option<String>,
+}
+
+impl LoginResponse {
+    // `# logresp N0CALL verified, server T2FINLAND`
+    fn parse(line: &str) -> Option<LoginResponse> {
+        let mut words = line.strip_prefix("# logresp ")?.split_whitespace();
+        let callsign = words.next()?.to_string();
+        let verified = match words.next()?.trim_end_matches(',') {
+            "verified" => true,
+            "unverified" => false,
+            _ => return None,
+        };
+        let server = match (words.next(), words.next()) {
+            (Some("server"), Some(name)) => Some(name.to_string()),
+            _ => None,
+        };
+        Some(LoginResponse { callsign, verified, server })
+    }
+}
+
+/// Connection to an APRS-IS server.
+///
+/// The connection is made on the first call to `next()`. Each line the server sends
+/// is parsed with the client's `Parser`; lines that fail to parse are returned as
+/// errors without affecting the connection.
+#[derive(Debug)]
+pub struct Client {
+    options: ClientBuilder,
+    parser: Parser,
+    stream: Option<BufReader<TcpStream>>,
+    login: Option<LoginResponse>,
+    backoff: Duration,
+}
+
+impl Client {
+    pub fn builder(callsign: &str) -> ClientBuilder {
+        ClientBuilder::new(callsign)
+    }
+
+    /// Whether there is a connection to the server.
+    pub fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// The server's answer to the login on the current connection, if it sent one.
+    pub fn login_response(&self) -> Option<&LoginResponse> {
+        self.login.as_ref()
+    }
+
+    /// Sends a packet in TNC2 format to the server. Only verified logins may send
+    /// packets, other ones are dropped by the server.
+    pub fn send(&mut self, packet: &str) -> Result<(), Error> {
+        if packet.contains(['\r', '\n']) {
+            return Err(Error::Other("packets must not contain line breaks".to_string()))
+        }
+        let result = match self.stream {
+            Some(ref mut stream) => write!(stream.get_mut(), "{}\r\n", packet),
+            None => Err(io::Error::new(io::ErrorKind::NotConnected, "not connected to APRS-IS")),
+        };
+        result.map_err(|err| self.lost(err))
+    }
+
+    /// Closes the connection. The next call to `next()` connects again, without delay.
+    pub fn disconnect(&mut self) {
+        self.stream = None;
+        self.login = None;
+    }
+
+    fn connect(&mut self) -> Result<(), Error> {
+        let options = &self.options;
+        let stream = connect(&options.server, options.timeout).map_err(Error::Io)?;
+        stream.set_read_timeout(Some(options.timeout)).map_err(Error::Io)?;
+        stream.set_write_timeout(Some(options.timeout)).map_err(Error::Io)?;
+        let mut login = format!("user {} pass {} vers {} {}", options.callsign, options.passcode,
+                                options.software.0, options.software.1);
+        if let Some(ref filter) = options.filter {
+            login.push_str(" filter ");
+            login.push_str(filter);
+        }
+        let mut stream = BufReader::new(stream);
+        write!(stream.get_mut(), "{}\r\n", login).map_err(Error::Io)?;
+        self.stream = Some(stream);
+        self.login = None;
+        Ok(())
+    }
+
+    // Drops the connection and makes the next attempt wait longer
+    fn lost(&mut self, err: io::Error) -> Error {
+        self.disconnect();
+        self.backoff = if self.backoff == Duration::from_secs(0) {
+            self.options.min_backoff
+        } else {
+            (self.backoff * 2).min(self.options.max_backoff)
+        };
+        Error::Io(err)
+    }
+}
+
+impl Iterator for Client {
+    type Item = Result<Packet, Error>;
+
+    /// Next packet from the server, connecting first if needed. Never returns `None`.
+    fn next(&mut self) -> Option<Result<Packet, Error>> {
+        loop {
+            if self.stream.is_none() {
+                if self.backoff > Duration::from_secs(0) {
+                    thread::sleep(self.backoff);
+                }
+                if let Err(err) = self.connect() {
+                    return Some(Err(match err {
+                        Error::Io(err) => self.lost(err),
+                        err => err,
+                    }))
+                }
+            }
+            let mut line = Vec::new();
+            let read = self.stream.as_mut().map_or(Ok(0), |stream| stream.read_until(b'\n', &mut line));
+            match read {
+                Ok(0) => {
+                    let err = io::Error::new(io::ErrorKind::UnexpectedEof, "server closed the connection");
+                    return Some(Err(self.lost(err)))
+                },
+                // a server that misses its keepalives is gone
+                Err(err) => return Some(Err(self.lost(err))),
+                Ok(_) => {},
+            }
+            while matches!(line.last(), Some(b'\n') | Some(b'\r')) {
+                line.pop();
+            }
+            if line.is_empty() {
+                continue
+            }
+            if line[0] == b'#' {
+                if let Some(login) = LoginResponse::parse(&String::from_utf8_lossy(&line)) {
+                    self.login = Some(login);
+                    self.backoff = Duration::from_secs(0);
+                }
+                continue
+            }
+            self.backoff = Duration::from_secs(0);
+            return Some(self.parser.parse(line))
+        }
+    }
+}
+
+// First of the server's addresses that accepts the connection
+fn connect(server: &str, timeout: Duration) -> io::Result<TcpStream> {
+    let mut last = io::Error::new(io::ErrorKind::NotFound, format!("no address for {}", server));
+    for addr in server.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last = err,
+        }
+    }
+    Err(last)
+}
+
+/// Options for a `Client`.
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    callsign: String,
+    passcode: i32,
+    server: String,
+    filter: Option<String>,
+    software: (String, String),
+    parser: Option<Parser>,
+    timeout: Duration,
+    min_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl ClientBuilder {
+    /// Receive-only login as `callsign` to `rotate.aprs2.net:14580`.
+    pub fn new(callsign: &str) -> ClientBuilder {
+        ClientBuilder {
+            callsign: callsign.to_string(),
+            passcode: -1,
+            server: "rotate.aprs2.net:14580".to_string(),
+            filter: None,
+            software: ("fap".to_string(), env!("CARGO_PKG_VERSION").to_string()),
+            parser: None,
+            timeout: Duration::from_secs(120),
+            min_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(300),
+        }
+    }
+
+    /// Server as `host:port`. Port 14580 is the user-defined filter port.
+    pub fn server(mut self, server: &str) -> ClientBuilder {
+        self.server = server.to_string();
+        self
+    }
+
+    /// Passcode of the callsign, needed to send packets, see `passcode()`. -1, the
+    /// default, logs in receive-only.
+    pub fn passcode(mut self, passcode: i32) -> ClientBuilder {
+        self.passcode = passcode;
+        self
+    }
+
+    /// Server-side filter, like `r/60.2/24.9/50 t/m`. `Filter` applies the same
+    /// filters locally.
+    pub fn filter(mut self, filter: &str) -> ClientBuilder {
+        self.filter = Some(filter.to_string());
+        self
+    }
+
+    /// Software name and version sent with the login, `fap` and the crate version
+    /// by default.
+    pub fn software(mut self, name: &str, version: &str) -> ClientBuilder {
+        self.software = (name.to_string(), version.to_string());
+        self
+    }
+
+    /// Parser for the received packets, `Parser::new()` by default.
+    pub fn parser(mut self, parser: Parser) -> ClientBuilder {
+        self.parser = Some(parser);
+        self
+    }
+
+    /// How long to wait for a connection and for data on it. Servers send a keepalive
+    /// comment about every 20 seconds, so a connection quiet for longer than this is
+    /// considered lost. Two minutes by default.
+    pub fn timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Delay before connecting again after a failure, doubling up to `max` with every
+    /// further failure. One second up to five minutes by default.
+    pub fn backoff(mut self, min: Duration, max: Duration) -> ClientBuilder {
+        self.min_backoff = min;
+        self.max_backoff = max.max(min);
+        self
+    }
+
+    pub fn build(mut self) -> Client {
+        let parser = self.parser.take().unwrap_or_default();
+        Client {
+            options: self,
+            parser,
+            stream: None,
+            login: None,
+            backoff: Duration::from_secs(0),
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt;
+use Error;
+
+/// APRS-IS q construct, telling how a packet entered APRS-IS. The station following
+/// it in the path is the IGate, client or server it entered at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QConstruct {
+    /// `qAC`, from the sending station's own verified login
+    Client,
+    /// `qAX`, from the sending station's own unverified login
+    Unverified,
+    /// `qAU`, sent to the server over UDP
+    Udp,
+    /// `qAo`, from a verified client for another station, without q construct
+    ClientOnly,
+    /// `qAO`, gated from RF by a receive-only or unverified IGate
+    ReceiveOnly,
+    /// `qAS`, from another server, or generated by the server itself
+    Server,
+    /// `qAr`, gated from RF by an IGate using the old `,I` construct
+    IgateLegacy,
+    /// `qAR`, gated from RF by a verified IGate
+    Igate,
+    /// `qAZ`, command to the server, not to be passed on
+    Command,
+    /// `qAI`, trace: every server adds itself to the path
+    Trace,
+}
+
+impl QConstruct {
+    /// Reads a path element like `qAR`. The construct is case sensitive.
+    pub fn parse(element: &str) -> Option<QConstruct> {
+        match element {
+            "qAC" => Some(QConstruct::Client),
+            "qAX" => Some(QConstruct::Unverified),
+            "qAU" => Some(QConstruct::Udp),
+            "qAo" => Some(QConstruct::ClientOnly),
+            "qAO" => Some(QConstruct::ReceiveOnly),
+            "qAS" => Some(QConstruct::Server),
+            "qAr" => Some(QConstruct::IgateLegacy),
+            "qAR" => Some(QConstruct::Igate),
+            "qAZ" => Some(QConstruct::Command),
+            "qAI" => Some(QConstruct::Trace),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            QConstruct::Client => "qAC",
+            QConstruct::Unverified => "qAX",
+            QConstruct::Udp => "qAU",
+            QConstruct::ClientOnly => "qAo",
+            QConstruct::ReceiveOnly => "qAO",
+            QConstruct::Server => "qAS",
+            QConstruct::IgateLegacy => "qAr",
+            QConstruct::Igate => "qAR",
+            QConstruct::Command => "qAZ",
+            QConstruct::Trace => "qAI",
+        }
+    }
+
+    /// The letter after `qA`, as used by the `q/` filter.
+    pub fn letter(&self) -> char {
+        self.as_str().as_bytes()[2] as char
+    }
+}
+
+impl fmt::Display for QConstruct {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Where a packet enters APRS-IS, for `q_algorithm()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entry<'a> {
+    /// Heard on RF by the IGate `igate`, logged in with a passcode if `verified`
+    Rf { igate: &'a str, verified: bool },
+    /// Received by the server `server` from a client logged in as `login`
+    Client { server: &'a str, login: &'a str, verified: bool },
+    /// Received by the server `server` over UDP
+    Udp { server: &'a str },
+}
+
+/// Adds the q construct to a packet in TNC2 format entering APRS-IS, following the
+/// APRS-IS q algorithm.
+///
+/// An IGate gating a packet from RF adds `qAR` (or `qAO` without a verified login)
+/// and its callsign:
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::aprsis::{q_algorithm, Entry};
+///
+/// let entry = Entry::Rf { igate: "N0CALL-10", verified: true };
+/// assert_eq!(q_algorithm("N1CALL>APRS,WIDE1-1*,WIDE2-1:>Hi", entry).unwrap(),
+///            "N1CALL>APRS,WIDE1-1*,WIDE2-1,qAR,N0CALL-10:>Hi");
+/// ```
+///
+/// A server marks packets from its clients with `qAC` or `qAX` and its own callsign
+/// if they are the client's own packets, and with `qAo` or `qAO` and the client's
+/// callsign otherwise. Packets gated with the old `CALL,I` construct get `qAr,CALL`
+/// instead. Packets from IGates that already carry a q construct keep it, except
+/// traces, which get the server's callsign appended. UDP submissions always get
+/// `qAU` and the server's callsign.
+///
+/// Fails for packets that must not be passed on: packets heard on RF that already
+/// went through APRS-IS, server commands, and packets that already passed the
+/// server or IGate.
+pub fn q_algorithm(packet: &str, entry: Entry) -> Result<String, Error> {
+    let colon = packet.find(':').ok_or_else(|| rejected("no header"))?;
+    let (header, body) = packet.split_at(colon);
+    let gt = header.find('>').ok_or_else(|| rejected("no destination"))?;
+    let source = &header[..gt];
+    if source.is_empty() {
+        return Err(rejected("no source"))
+    }
+    let mut path: Vec<&str> = header[gt + 1..].split(',').collect();
+    let destination = path.remove(0);
+    let q = path.iter().position(|p| QConstruct::parse(p).is_some());
+    let construct = q.and_then(|i| QConstruct::parse(path[i]));
+    if construct == Some(QConstruct::Command) {
+        return Err(rejected("server commands are not passed on"))
+    }
+
+    let me = match entry {
+        Entry::Rf { igate, .. } => igate,
+        Entry::Client { server, .. } | Entry::Udp { server } => server,
+    };
+    if q.is_some_and(|i| path[i + 1..].iter().any(|p| p.eq_ignore_ascii_case(me))) {
+        return Err(rejected("loop"))
+    }
+
+    let mut path: Vec<String> = path.iter().map(|p| p.to_string()).collect();
+    let replace = |path: &mut Vec<String>, construct: QConstruct, call: &str| {
+        path.truncate(q.unwrap_or(path.len()));
+        path.push(construct.to_string());
+        path.push(call.to_string());
+    };
+    match entry {
+        Entry::Rf { igate, verified } => {
+            if q.is_some() || path.iter().any(|p| matches!(p.trim_end_matches('*'), "TCPIP" | "TCPXX")) {
+                return Err(rejected("packet already went through APRS-IS"))
+            }
+            let construct = if verified { QConstruct::Igate } else { QConstruct::ReceiveOnly };
+            replace(&mut path, construct, igate);
+        },
+        Entry::Udp { server } => replace(&mut path, QConstruct::Udp, server),
+        Entry::Client { server, login, verified } => {
+            let own = source.eq_ignore_ascii_case(login);
+            match construct {
+                Some(QConstruct::Trace) => path.push(server.to_string()),
+                _ if own => {
+                    let construct = if verified { QConstruct::Client } else { QConstruct::Unverified };
+                    replace(&mut path, construct, server);
+                },
+                // the IGate already told how it got the packet
+                Some(_) if verified => {},
+                _ => {
+                    // `CALL,I` is the old way of saying `qAr,CALL`
+                    let legacy = path.iter().position(|p| p == "I").filter(|&i| i > 0 && q.is_none());
+                    match legacy {
+                        Some(i) if verified => {
+                            let igate = path[i - 1].clone();
+                            path.truncate(i - 1);
+                            path.push(QConstruct::IgateLegacy.to_string());
+                            path.push(igate);
+                        },
+                        _ => {
+                            if let Some(i) = legacy {
+                                path.truncate(i - 1);
+                            }
+                            let construct = if verified { QConstruct::ClientOnly } else { QConstruct::ReceiveOnly };
+                            replace(&mut path, construct, login);
+                        },
+                    }
+                },
+            }
+        },
+    }
+    let mut out = format!("{}>{}", source, destination);
+    for p in &path {
+        out.push(',');
+        out.push_str(p);
+    }
+    out.push_str(body);
+    Ok(out)
+}
+
+fn rejected(reason: &str) -> Error {
+    Error::Other(format!("packet not passed on to APRS-IS: {}", reason))
+}
@@ -0,0 +1,236 @@
+//! WIDEn-N digipeating.
+//!
+//! `Digi` decides whether a packet heard on RF is repeated, and rewrites its path the
+//! way a digipeater does: the element it acts on gets the H-bit (`*`), `WIDEn-N` has
+//! its hop counter decremented and the digipeater's callsign is inserted before it.
+//! Like `igate::Igate` it does no I/O: packets are passed in with the time they were
+//! heard, and the lines to transmit are returned, in TNC2 format with every used path
+//! element marked, ready for `tnc2_to_ax25()` or `tnc2_to_kiss()`.
+//!
+//! ```rust
+//! extern crate fap;
+//! use fap::Packet;
+//! use fap::digipeater::Digi;
+//! use std::time::{Duration, UNIX_EPOCH};
+//!
+//! let mut digi = Digi::new("N0CALL-2");
+//! let now = UNIX_EPOCH + Duration::from_secs(1_500_000_000);
+//! let packet = Packet::new("N1CALL-7>APRS,WIDE1-1,WIDE2-1:>Hello").unwrap();
+//! assert_eq!(digi.digipeat(&packet, now).as_deref(), Some("N1CALL-7>APRS,N0CALL-2*,WIDE1*,WIDE2-1:>Hello"));
+//! // heard again, repeated by another digipeater
+//! let packet = Packet::new("N1CALL-7>APRS,N0CALL-3*,WIDE1*,WIDE2-1:>Hello").unwrap();
+//! assert_eq!(digi.digipeat(&packet, now), None);
+//! ```
+
+use aprs::Packet as AprsPacket;
+use aprsis::QConstruct;
+use bind::fap_count_digihops;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+use {init, Packet};
+
+// AX.25 allows at most 8 digipeaters
+const MAX_PATH_LEN: usize = 8;
+
+/// Digipeater configuration and the packets it has recently repeated.
+#[derive(Debug, Clone)]
+pub struct Digi {
+    callsign: String,
+    aliases: Vec<String>,
+    max_hops: u32,
+    fill_in: bool,
+    viscous: Duration,
+    preemptive: bool,
+    dupe_window: Duration,
+    repeated: HashMap<String, SystemTime>,
+    // packets waiting out the viscous delay: due time, duplicate key and line
+    held: Vec<(SystemTime, String, String)>,
+}
+
+impl Digi {
+    /// Wide digipeater with the callsign `callsign`, repeating `WIDEn-N` for up to
+    /// 3 hops in total.
+    pub fn new(callsign: &str) -> Digi {
+        Digi {
+            callsign: callsign.to_ascii_uppercase(),
+            aliases: Vec::new(),
+            max_hops: 3,
+            fill_in: false,
+            viscous: Duration::from_secs(0),
+            preemptive: false,
+            dupe_window: Duration::from_secs(30),
+            repeated: HashMap::new(),
+            held: Vec::new(),
+        }
+    }
+
+    /// Other names the digipeater answers to, like `RELAY` or a club callsign. They
+    /// are replaced by the digipeater's callsign when used.
+    pub fn aliases(mut self, aliases: &[&str]) -> Digi {
+        self.aliases = aliases.iter().map(|a| a.to_ascii_uppercase()).collect();
+        self
+    }
+
+    /// Largest number of hops a packet may take, counting the ones it already took
+    /// and the ones its path still asks for. Packets asking for more are not repeated,
+    /// nor are `WIDEn-N` packets with anything but AX.25 callsigns in their path.
+    pub fn max_hops(mut self, hops: u32) -> Digi {
+        self.max_hops = hops;
+        self
+    }
+
+    /// Fill-in digipeaters only repeat `WIDE1-1`, for stations that the wide
+    /// digipeaters do not hear.
+    pub fn fill_in(mut self, fill_in: bool) -> Digi {
+        self.fill_in = fill_in;
+        self
+    }
+
+    /// Holds `WIDEn-N` packets for `delay` before repeating them, and drops them if
+    /// another digipeater repeats them first. Collect the held packets with `poll()`.
+    pub fn viscous(mut self, delay: Duration) -> Digi {
+        self.viscous = delay;
+        self
+    }
+
+    /// Also acts on the digipeater's callsign or alias further down the path,
+    /// marking the unused elements before it as used.
+    pub fn preemptive(mut self, preemptive: bool) -> Digi {
+        self.preemptive = preemptive;
+        self
+    }
+
+    /// How long the same packet from the same station is not repeated again,
+    /// 30 seconds by default.
+    pub fn dupe_window(mut self, window: Duration) -> Digi {
+        self.dupe_window = window;
+        self
+    }
+
+    /// Decides whether to repeat a packet heard at `now`. Returns the line to transmit,
+    /// or `None` if the packet is not repeated, or held for the viscous delay.
+    pub fn digipeat(&mut self, packet: &Packet, now: SystemTime) -> Option<String> {
+        self.expire(now);
+        let key = format!("{}>{}:{}", packet.source(), packet.destination()?, packet.body());
+        if self.repeated.contains_key(&key) {
+            // someone else repeated it before our viscous delay ran out
+            self.held.retain(|(_, held, _)| *held != key);
+            return None
+        }
+        let (path, wide) = self.rewrite(packet)?;
+        let mut line = format!("{}>{}", packet.source(), packet.destination()?);
+        for element in &path {
+            line.push(',');
+            line.push_str(element);
+        }
+        line.push(':');
+        line.push_str(&packet.body());
+        self.repeated.insert(key.clone(), now);
+        if wide && self.viscous > Duration::from_secs(0) {
+            self.held.push((now + self.viscous, key, line));
+            return None
+        }
+        Some(line)
+    }
+
+    /// Held packets whose viscous delay is over at `now`, to be transmitted.
+    pub fn poll(&mut self, now: SystemTime) -> Vec<String> {
+        let (due, held) = self.held.drain(..).partition(|&(at, _, _)| at <= now);
+        self.held = held;
+        due.into_iter().map(|(_, _, line)| line).collect()
+    }
+
+    /// When the next held packet is due, if any.
+    pub fn next_due(&self) -> Option<SystemTime> {
+        self.held.iter().map(|&(at, _, _)| at).min()
+    }
+
+    // New path for a packet to repeat, and whether a WIDEn-N element was used
+    fn rewrite(&self, packet: &Packet) -> Option<(Vec<String>, bool)> {
+        if packet.source().eq_ignore_ascii_case(&self.callsign) {
+            return None
+        }
+        let path = packet.path();
+        // packets from APRS-IS come to RF as third-party packets only
+        if path.iter().any(|p| matches!(p.trim_end_matches('*'), "TCPIP" | "TCPXX") || QConstruct::parse(p).is_some()) {
+            return None
+        }
+        let mut path: Vec<String> = path.iter().map(|p| p.to_ascii_uppercase()).collect();
+        let next = path.iter().rposition(|p| p.ends_with('*')).map_or(0, |i| i + 1);
+        if next == path.len() || path[..next].iter().any(|p| p.trim_end_matches('*') == self.callsign) {
+            return None
+        }
+
+        let mine = |p: &str| p == self.callsign || self.aliases.iter().any(|a| a == p);
+        let target = if mine(&path[next]) {
+            Some(next)
+        } else if self.preemptive {
+            path[next + 1..].iter().position(|p| mine(p)).map(|i| i + next + 1)
+        } else {
+            None
+        };
+        if let Some(target) = target {
+            for element in &mut path[..target] {
+                mark(element);
+            }
+            path[target] = format!("{}*", self.callsign);
+            return Some((path, false))
+        }
+
+        let (n, hops) = wide(&path[next])?;
+        if (self.fill_in && n != 1) || hops > n {
+            return None
+        }
+        let requested: u32 = path[next..].iter().filter_map(|p| wide(p)).map(|(_, hops)| hops).sum();
+        if hops_done(packet, &path)? + requested > self.max_hops {
+            return None
+        }
+        for element in &mut path[..next] {
+            mark(element);
+        }
+        path[next] = if hops == 1 { format!("WIDE{}*", n) } else { format!("WIDE{}-{}", n, hops - 1) };
+        if path.len() < MAX_PATH_LEN {
+            path.insert(next, format!("{}*", self.callsign));
+        }
+        Some((path, true))
+    }
+
+    // Forgets packets repeated before the dupe window
+    fn expire(&mut self, now: SystemTime) {
+        let window = self.dupe_window;
+        self.repeated.retain(|_, t| now.duration_since(*t).map_or(true, |age| age <= window));
+    }
+}
+
+// n and N of an unused WIDEn-N element
+fn wide(element: &str) -> Option<(u32, u32)> {
+    let rest = element.strip_prefix("WIDE")?;
+    let (n, hops) = rest.split_at(rest.find('-')?);
+    let n: u32 = n.parse().ok()?;
+    let hops: u32 = hops[1..].parse().ok()?;
+    if !(1..=7).contains(&n) || !(1..=7).contains(&hops) {
+        return None
+    }
+    Some((n, hops))
+}
+
+fn mark(element: &mut String) {
+    if !element.ends_with('*') {
+        element.push('*');
+    }
+}
+
+// Hops the packet already took, `None` if libfap can not count them because of an
+// invalid path element. libfap counts both the callsign a digipeater inserted and the
+// WIDEn-N element following it, count those once.
+fn hops_done(packet: &Packet, path: &[String]) -> Option<u32> {
+    init();
+    let counted = unsafe { fap_count_digihops(packet.ptr) };
+    if counted < 0 {
+        return None
+    }
+    let traced = path.windows(2)
+        .filter(|pair| pair[0].ends_with('*') && !pair[0].starts_with("WIDE") && pair[1].starts_with("WIDE"))
+        .count();
+    Some((counted as u32).saturating_sub(traced as u32))
+}
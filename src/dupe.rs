@@ -0,0 +1,126 @@
+//! Duplicate packet detection.
+
+use aprs::Packet as AprsPacket;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, SystemTime};
+use Packet;
+
+/// Remembers recently seen packets to drop the copies arriving through other
+/// digipeaters and IGates, following the APRS-IS dupe rule: two packets are the same
+/// if they have the same source, destination and body, whatever their paths. Trailing
+/// whitespace of the body is ignored.
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::{DupeChecker, Packet};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let mut dupes = DupeChecker::new(Duration::from_secs(30));
+/// let now = UNIX_EPOCH + Duration::from_secs(1_500_000_000);
+/// let first = Packet::new("N1CALL>APRS,WIDE1-1,qAR,N0CALL-10:>Hello").unwrap();
+/// let second = Packet::new("N1CALL>APRS,DIGI*,WIDE1*,qAR,N0CALL-11:>Hello ").unwrap();
+/// assert!(!dupes.is_dupe(&first, now));
+/// assert!(dupes.is_dupe(&second, now + Duration::from_secs(5)));
+/// ```
+///
+/// Only a hash of each packet is kept, in a set bounded by `capacity()`, so the
+/// checker can run for a long time on busy feeds.
+#[derive(Debug, Clone)]
+pub struct DupeChecker {
+    window: Duration,
+    capacity: usize,
+    seen: HashSet<u64>,
+    // hashes in the order they were seen, to expire the oldest first
+    order: VecDeque<(SystemTime, u64)>,
+}
+
+impl DupeChecker {
+    /// Checker treating packets seen within `window` as duplicates. APRS-IS uses
+    /// 30 seconds.
+    pub fn new(window: Duration) -> DupeChecker {
+        DupeChecker {
+            window,
+            capacity: 100_000,
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Largest number of packets remembered, 100000 by default. The oldest are
+    /// forgotten first, even if they are still within the window.
+    pub fn capacity(mut self, capacity: usize) -> DupeChecker {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Whether the packet was seen within the window before `now`. The packet is
+    /// remembered if it was not, so that its copies are caught.
+    pub fn is_dupe(&mut self, packet: &Packet, now: SystemTime) -> bool {
+        let destination = packet.destination().unwrap_or_default();
+        self.check(&packet.source(), &destination, &packet.body(), now)
+    }
+
+    /// Like `is_dupe()` for a packet in TNC2 format, without parsing it. Lines that
+    /// are not packets are never duplicates.
+    pub fn is_dupe_tnc2(&mut self, packet: &str, now: SystemTime) -> bool {
+        let colon = match packet.find(':') {
+            Some(colon) => colon,
+            None => return false,
+        };
+        let (header, body) = (&packet[..colon], &packet[colon + 1..]);
+        let (source, path) = match header.find('>') {
+            Some(gt) => (&header[..gt], &header[gt + 1..]),
+            None => return false,
+        };
+        let destination = path.split(',').next().unwrap_or(path);
+        self.check(source, destination, body, now)
+    }
+
+    /// Number of packets remembered.
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    /// Forgets all packets.
+    pub fn clear(&mut self) {
+        self.seen.clear();
+        self.order.clear();
+    }
+
+    fn check(&mut self, source: &str, destination: &str, body: &str, now: SystemTime) -> bool {
+        self.expire(now);
+        let mut hasher = DefaultHasher::new();
+        (source, destination, body.trim_end()).hash(&mut hasher);
+        let hash = hasher.finish();
+        if !self.seen.insert(hash) {
+            return true
+        }
+        self.order.push_back((now, hash));
+        while self.order.len() > self.capacity {
+            self.forget_oldest();
+        }
+        false
+    }
+
+    // Forgets packets older than the window
+    fn expire(&mut self, now: SystemTime) {
+        while let Some(&(seen, _)) = self.order.front() {
+            if now.duration_since(seen).map_or(true, |age| age <= self.window) {
+                break
+            }
+            self.forget_oldest();
+        }
+    }
+
+    fn forget_oldest(&mut self) {
+        if let Some((_, hash)) = self.order.pop_front() {
+            self.seen.remove(&hash);
+        }
+    }
+}
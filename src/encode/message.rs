@@ -0,0 +1,198 @@
+use super::{digit36, invalid};
+use Error;
+
+/// Longest message or bulletin text that fits in one packet.
+pub const MAX_MESSAGE_LEN: usize = 67;
+
+/// Message to another station.
+///
+/// Texts longer than 67 characters are split at spaces into several packets, each of
+/// them with its own message ID:
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::{Message, MessageIds, PacketBuilder};
+///
+/// let mut ids = MessageIds::new();
+/// let message = Message::new("N0CALL-9", "Hello").reply_ack("");
+/// let raw = PacketBuilder::new("N1CALL").message(&message, Some(&mut ids)).unwrap();
+/// assert_eq!(raw, ["N1CALL>APRS::N0CALL-9 :Hello{01}"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    addressee: String,
+    text: String,
+    reply_ack: Option<String>,
+}
+
+impl Message {
+    pub fn new(addressee: &str, text: &str) -> Message {
+        Message {
+            addressee: addressee.to_string(),
+            text: text.to_string(),
+            reply_ack: None,
+        }
+    }
+
+    /// Use the reply-ack format, acknowledging the message `id` from the addressee
+    /// along with this one. An empty `id` only announces reply-ack support. Requires
+    /// the two character IDs `MessageIds` generates.
+    pub fn reply_ack(mut self, id: &str) -> Message {
+        self.reply_ack = Some(id.to_string());
+        self
+    }
+
+    pub(crate) fn encode(&self, mut ids: Option<&mut MessageIds>) -> Result<Vec<String>, Error> {
+        check_message_text(&self.text)?;
+        if let Some(ref reply_ack) = self.reply_ack {
+            if ids.is_none() {
+                return invalid("reply-acks need message IDs".to_string())
+            }
+            if !reply_ack.is_empty() {
+                check_id(reply_ack)?;
+            }
+        }
+        let addressee = addressee(&self.addressee)?;
+        Ok(split(&self.text).into_iter()
+            .map(|part| {
+                let mut body = format!(":{}:{}", addressee, part);
+                if let Some(ids) = ids.as_mut() {
+                    body.push('{');
+                    body.push_str(&ids.next_id());
+                    if let Some(ref reply_ack) = self.reply_ack {
+                        body.push('}');
+                        body.push_str(reply_ack);
+                    }
+                }
+                body
+            })
+            .collect())
+    }
+}
+
+/// Generates message IDs, two base-36 digits from `01` to `ZZ` that then start over.
+///
+/// Two digits keep the IDs usable with reply-acks. A station should keep using the
+/// same generator for all its messages, so that retries and new messages can be told
+/// apart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageIds {
+    next: u32,
+}
+
+impl MessageIds {
+    pub fn new() -> MessageIds {
+        MessageIds { next: 1 }
+    }
+
+    /// Continue after `id`, as when restarting with the last ID used before.
+    pub fn after(id: &str) -> Option<MessageIds> {
+        if id.len() != 2 || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None
+        }
+        let last = u32::from_str_radix(id, 36).ok()?;
+        let mut ids = MessageIds { next: last };
+        ids.next_id();
+        Some(ids)
+    }
+
+    pub fn next_id(&mut self) -> String {
+        let id = self.next;
+        self.next = id % (36 * 36 - 1) + 1;
+        format!("{}{}", digit36(id / 36), digit36(id % 36))
+    }
+}
+
+impl Default for MessageIds {
+    fn default() -> MessageIds {
+        MessageIds::new()
+    }
+}
+
+/// Bulletins and announcements, sent to everybody rather than one station.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bulletin {
+    /// General bulletin `BLN0` to `BLN9`, the digit numbering the lines.
+    General(u8),
+    /// Group bulletin like `BLN3WX`: line number and group name of up to 5 characters.
+    Group(u8, String),
+    /// Announcement `BLNA` to `BLNZ`.
+    Announcement(char),
+    /// National Weather Service bulletin like `NWS-WARN`.
+    Nws(String),
+}
+
+impl Bulletin {
+    fn addressee(&self) -> Result<String, Error> {
+        let name_ok = |name: &str, max| !name.is_empty() && name.len() <= max
+            && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+        match *self {
+            Bulletin::General(n) if n < 10 => Ok(format!("BLN{}", n)),
+            Bulletin::Group(n, ref group) if n < 10 && name_ok(group, 5) => Ok(format!("BLN{}{}", n, group)),
+            Bulletin::Announcement(c) if c.is_ascii_uppercase() => Ok(format!("BLN{}", c)),
+            Bulletin::Nws(ref kind) if name_ok(kind, 5) => Ok(format!("NWS-{}", kind)),
+            _ => invalid(format!("invalid bulletin {:?}", self)),
+        }
+    }
+
+    pub(crate) fn encode(&self, text: &str) -> Result<String, Error> {
+        check_message_text(text)?;
+        if text.chars().count() > MAX_MESSAGE_LEN {
+            return invalid(format!("bulletin text is longer than {} characters", MAX_MESSAGE_LEN))
+        }
+        Ok(format!(":{}:{}", addressee(&self.addressee()?)?, text))
+    }
+}
+
+// `:ackID` and `:rejID`
+pub(crate) fn ack(what: &str, addressee_call: &str, id: &str) -> Result<String, Error> {
+    check_id(id)?;
+    Ok(format!(":{}:{}{}", addressee(addressee_call)?, what, id))
+}
+
+// Addressee padded to 9 characters
+pub(crate) fn addressee(call: &str) -> Result<String, Error> {
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    if call.is_empty() || call.len() > 9 || !call.chars().all(valid) {
+        return invalid(format!("invalid addressee {:?}", call))
+    }
+    Ok(format!("{:9}", call))
+}
+
+// Message IDs are 1 to 5 letters and digits
+fn check_id(id: &str) -> Result<(), Error> {
+    if id.is_empty() || id.len() > 5 || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return invalid(format!("invalid message ID {:?}", id))
+    }
+    Ok(())
+}
+
+// `{` starts the message ID, `|` and `~` are not allowed in messages
+pub(crate) fn check_message_text(text: &str) -> Result<(), Error> {
+    if text.trim().is_empty() {
+        return invalid("message text must not be empty".to_string())
+    }
+    if text.chars().any(|c| c.is_control() || c == '{' || c == '|' || c == '~') {
+        return invalid(format!("message text {:?} contains control characters, '{{', '|' or '~'", text))
+    }
+    Ok(())
+}
+
+// Parts of at most 67 characters, broken at spaces where possible
+fn split(text: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut rest = text.trim();
+    while rest.chars().count() > MAX_MESSAGE_LEN {
+        let limit = rest.char_indices().nth(MAX_MESSAGE_LEN).unwrap().0;
+        // a space right after the limit still ends the part
+        let end = rest.char_indices().nth(MAX_MESSAGE_LEN + 1).map_or(rest.len(), |(i, _)| i);
+        let (part, next) = match rest[..end].rfind(' ') {
+            Some(space) if space > 0 => (&rest[..space], &rest[space..]),
+            _ => (&rest[..limit], &rest[limit..]),
+        };
+        parts.push(part.trim_end().to_string());
+        rest = next.trim_start();
+    }
+    parts.push(rest.to_string());
+    parts
+}
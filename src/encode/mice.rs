@@ -0,0 +1,238 @@
+use super::{altitude_base91, check_text, invalid, symbol_is_valid, telemetry_base91};
+use Error;
+
+const KNOT_TO_KMH: f64 = 1.852;
+
+/// Mic-E status message, carried in the destination callsign.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MicEMessage {
+    OffDuty,
+    EnRoute,
+    InService,
+    Returning,
+    Committed,
+    Special,
+    Priority,
+    Emergency,
+    /// Custom message 0 to 6.
+    Custom(u8),
+}
+
+impl MicEMessage {
+    /// Message from the bits `Packet::messagebits()` reports, like `110` or `222`.
+    pub fn from_bits(bits: &str) -> Option<MicEMessage> {
+        use self::MicEMessage::*;
+        let standard = [OffDuty, EnRoute, InService, Returning, Committed, Special, Priority];
+        if bits == "000" {
+            return Some(Emergency)
+        }
+        (0..7).find(|&i| bits == pattern(i, '1'))
+            .map(|i| standard[i as usize])
+            .or_else(|| (0..7).find(|&i| bits == pattern(i, '2')).map(Custom))
+    }
+
+    // Message bits A, B and C as '0', '1' (standard) or '2' (custom)
+    fn bits(&self) -> Result<String, Error> {
+        use self::MicEMessage::*;
+        Ok(match *self {
+            OffDuty => pattern(0, '1'),
+            EnRoute => pattern(1, '1'),
+            InService => pattern(2, '1'),
+            Returning => pattern(3, '1'),
+            Committed => pattern(4, '1'),
+            Special => pattern(5, '1'),
+            Priority => pattern(6, '1'),
+            Emergency => "000".to_string(),
+            Custom(n) if n < 7 => pattern(n, '2'),
+            Custom(n) => return invalid(format!("invalid custom Mic-E message {}", n)),
+        })
+    }
+}
+
+// Message n has the bits of 7 - n, with `one` for the set bits
+fn pattern(n: u8, one: char) -> String {
+    (0..3).rev().map(|bit| if (7 - n) >> bit & 1 == 1 { one } else { '0' }).collect()
+}
+
+/// Mic-E position report, in the same units `Packet` reports them.
+///
+/// Mic-E packs latitude and the status message into the destination callsign and
+/// the rest of the position into 9 bytes, which makes it the most compact format on
+/// RF. `PacketBuilder::mice()` replaces the builder's destination with the encoded one.
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::{MicEMessage, MicEReport, PacketBuilder};
+///
+/// let report = MicEReport::new(33.4275, -112.1290)
+///     .symbol('/', '>')
+///     .message(MicEMessage::EnRoute)
+///     .course_speed(251, 37.0);
+/// let raw = PacketBuilder::new("N0CALL").mice(&report).unwrap();
+/// assert_eq!(raw, "N0CALL>SS2UVU:`(_fn\"O>/");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct MicEReport {
+    latitude: f64,
+    longitude: f64,
+    symbol_table: char,
+    symbol_code: char,
+    message: MicEMessage,
+    ambiguity: u8,
+    course: u32,
+    speed: f64,
+    altitude: Option<f64>,
+    telemetry: Option<(u32, Vec<u32>, Option<u8>)>,
+    comment: String,
+}
+
+impl MicEReport {
+    /// Report with the `/` symbol and `OffDuty` message, not moving.
+    pub fn new(latitude: f64, longitude: f64) -> MicEReport {
+        MicEReport {
+            latitude,
+            longitude,
+            symbol_table: '/',
+            symbol_code: '/',
+            message: MicEMessage::OffDuty,
+            ambiguity: 0,
+            course: 0,
+            speed: 0.0,
+            altitude: None,
+            telemetry: None,
+            comment: String::new(),
+        }
+    }
+
+    /// Symbol table (`/`, `\` or an overlay character) and symbol code.
+    pub fn symbol(mut self, table: char, code: char) -> MicEReport {
+        self.symbol_table = table;
+        self.symbol_code = code;
+        self
+    }
+
+    pub fn message(mut self, message: MicEMessage) -> MicEReport {
+        self.message = message;
+        self
+    }
+
+    /// Number of position digits to blank out, 0 to 4.
+    pub fn ambiguity(mut self, ambiguity: u8) -> MicEReport {
+        self.ambiguity = ambiguity;
+        self
+    }
+
+    /// Course in degrees (1 to 360, 0 if unknown) and speed in km/h.
+    pub fn course_speed(mut self, course: u32, speed: f64) -> MicEReport {
+        self.course = course;
+        self.speed = speed;
+        self
+    }
+
+    /// Altitude in meters, -10000 or higher.
+    pub fn altitude(mut self, altitude: f64) -> MicEReport {
+        self.altitude = Some(altitude);
+        self
+    }
+
+    /// Base-91 comment telemetry: sequence number, one to five values and optionally
+    /// eight bits (the first bit is the lowest one). All numbers go up to 8280.
+    pub fn telemetry(mut self, seq: u32, values: &[u32], bits: Option<u8>) -> MicEReport {
+        self.telemetry = Some((seq, values.to_vec(), bits));
+        self
+    }
+
+    pub fn comment(mut self, comment: &str) -> MicEReport {
+        self.comment = comment.to_string();
+        self
+    }
+
+    // Destination callsign and information field
+    pub(crate) fn encode(&self) -> Result<(String, String), Error> {
+        check_text("comment", &self.comment)?;
+        if !(-90.0..=90.0).contains(&self.latitude) || !(-180.0..=180.0).contains(&self.longitude) {
+            return invalid(format!("invalid position {}, {}", self.latitude, self.longitude))
+        }
+        if !symbol_is_valid(self.symbol_table, self.symbol_code) {
+            return invalid(format!("invalid symbol {}{}", self.symbol_table, self.symbol_code))
+        }
+        if self.ambiguity > 4 {
+            return invalid(format!("invalid position ambiguity {}", self.ambiguity))
+        }
+        if self.course > 360 {
+            return invalid(format!("invalid course {}", self.course))
+        }
+        let knots = (self.speed / KNOT_TO_KMH).round();
+        if !(0.0..=799.0).contains(&knots) {
+            return invalid(format!("invalid speed {}", self.speed))
+        }
+        let knots = knots as u32;
+
+        let (lat_degrees, lat_minutes) = hundredths(self.latitude, 90 * 6000);
+        // 180 degrees would not decode, 179.9999 is close enough
+        let (lon_degrees, mut lon_minutes) = hundredths(self.longitude, 180 * 6000 - 1);
+
+        // Latitude digits, message bits, north, longitude offset and west
+        let lat = format!("{:02}{:04}", lat_degrees, lat_minutes);
+        let lon_offset = !(10..100).contains(&lon_degrees);
+        let flags = self.message.bits()?
+            + if self.latitude >= 0.0 { "1" } else { "0" }
+            + if lon_offset { "1" } else { "0" }
+            + if self.longitude < 0.0 { "1" } else { "0" };
+        let blanked = 6 - self.ambiguity as usize;
+        let destination = lat.bytes().zip(flags.bytes()).enumerate()
+            .map(|(i, (digit, flag))| {
+                let digit = digit - b'0';
+                match (flag, i < blanked) {
+                    (b'1', true) => (b'P' + digit) as char,
+                    (b'2', true) => (b'A' + digit) as char,
+                    (_, true) => (b'0' + digit) as char,
+                    (b'1', false) => 'Z',
+                    (b'2', false) => 'K',
+                    (_, false) => 'L',
+                }
+            })
+            .collect::<String>();
+
+        // Longitude digits hidden by ambiguity are not sent either
+        lon_minutes -= lon_minutes % [1, 10, 100, 1000, 6000][self.ambiguity as usize];
+        let degrees = match lon_degrees {
+            0..=9 => lon_degrees + 118,
+            10..=99 => lon_degrees + 28,
+            100..=109 => lon_degrees + 8,
+            _ => lon_degrees - 72,
+        };
+        let minutes = match lon_minutes / 100 {
+            m @ 0..=9 => m + 88,
+            m => m + 28,
+        };
+        // Speeds below 200 knots and the hundreds of course are offset to keep clear of
+        // control characters. Like the minutes, courses ending in 00 to 03 still give bytes
+        // 28 to 31 in the last place, which the specification allows.
+        let sp = if knots < 200 { knots / 10 + 108 } else { knots / 10 + 28 };
+        let dc = knots % 10 * 10 + self.course / 100 + 4 + 28;
+        let se = self.course % 100 + 28;
+
+        let mut info = String::from("`");
+        for b in &[degrees, minutes, lon_minutes % 100 + 28, sp, dc, se] {
+            info.push(*b as u8 as char);
+        }
+        info.push(self.symbol_code);
+        info.push(self.symbol_table);
+        if let Some(altitude) = self.altitude {
+            info.push_str(&altitude_base91(altitude)?);
+            info.push('}');
+        }
+        info.push_str(&self.comment);
+        if let Some((seq, ref values, bits)) = self.telemetry {
+            info.push_str(&telemetry_base91(seq, values, bits)?);
+        }
+        Ok((destination, info))
+    }
+}
+
+// Whole degrees and hundredths of minutes, up to `max` hundredths
+fn hundredths(value: f64, max: u32) -> (u32, u32) {
+    let total = ((value.abs() * 6000.0).round() as u32).min(max);
+    (total / 6000, total % 6000)
+}
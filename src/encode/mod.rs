@@ -0,0 +1,292 @@
+//! Generating APRS packets in TNC2 format.
+
+use humantime;
+use std::time::{SystemTime, UNIX_EPOCH};
+use Error;
+
+mod message;
+mod mice;
+mod object;
+mod position;
+mod status;
+mod telemetry;
+mod weather;
+
+pub use self::message::{Bulletin, Message, MessageIds, MAX_MESSAGE_LEN};
+pub use self::mice::{MicEMessage, MicEReport};
+pub use self::object::{ItemReport, ObjectReport};
+pub use self::position::{PositionReport, Phg};
+pub use self::status::StatusReport;
+pub use self::telemetry::{TelemetryDefinition, TelemetryReport};
+pub use self::weather::WeatherReport;
+
+/// Builds TNC2 packets from a source callsign, destination and path.
+///
+/// The packet body is given to one of the encoding methods, which return the complete
+/// packet ready to be sent to APRS-IS or converted for a TNC with `tnc2_to_kiss()`:
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::{PacketBuilder, PositionReport};
+///
+/// let builder = fap::PacketBuilder::new("N0CALL").path(&["WIDE1-1"]);
+/// let raw = builder.position(&PositionReport::new(49.0583, -72.0292).symbol('/', '-')).unwrap();
+/// assert_eq!(raw, "N0CALL>APRS,WIDE1-1:!4903.50N/07201.75W-");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PacketBuilder {
+    source: String,
+    destination: String,
+    path: Vec<String>,
+}
+
+impl PacketBuilder {
+    /// Packets from `source` to the generic `APRS` destination, with an empty path.
+    pub fn new(source: &str) -> PacketBuilder {
+        PacketBuilder {
+            source: source.to_string(),
+            destination: "APRS".to_string(),
+            path: Vec::new(),
+        }
+    }
+
+    /// Destination callsign, usually a software version identifier like `APZxxx`.
+    pub fn destination(mut self, destination: &str) -> PacketBuilder {
+        self.destination = destination.to_string();
+        self
+    }
+
+    /// Digipeater path, like `WIDE1-1,WIDE2-1`, in TNC2 format.
+    pub fn path(mut self, path: &[&str]) -> PacketBuilder {
+        self.path = path.iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    /// Position report.
+    pub fn position(&self, report: &PositionReport) -> Result<String, Error> {
+        self.packet(&self.destination, &report.encode()?)
+    }
+
+    /// Weather report, positionless or with position.
+    pub fn weather(&self, report: &WeatherReport) -> Result<String, Error> {
+        self.packet(&self.destination, &report.encode()?)
+    }
+
+    /// Mic-E position report. The destination is part of the report and replaces the
+    /// builder's one.
+    pub fn mice(&self, report: &MicEReport) -> Result<String, Error> {
+        let (destination, info) = report.encode()?;
+        self.packet(&destination, &info)
+    }
+
+    /// Object report, alive or killed.
+    pub fn object(&self, object: &ObjectReport) -> Result<String, Error> {
+        self.packet(&self.destination, &object.encode()?)
+    }
+
+    /// Item report, alive or killed.
+    pub fn item(&self, item: &ItemReport) -> Result<String, Error> {
+        self.packet(&self.destination, &item.encode()?)
+    }
+
+    /// `T#` telemetry report.
+    pub fn telemetry(&self, report: &TelemetryReport) -> Result<String, Error> {
+        self.packet(&self.destination, &report.encode()?)
+    }
+
+    /// Telemetry metadata message, addressed to the source station.
+    pub fn telemetry_definition(&self, definition: &TelemetryDefinition) -> Result<String, Error> {
+        self.packet(&self.destination, &definition.encode(&self.source)?)
+    }
+
+    /// Status report.
+    pub fn status(&self, report: &StatusReport) -> Result<String, Error> {
+        self.packet(&self.destination, &report.encode()?)
+    }
+
+    /// Station capabilities `<NAME,NAME=value,...`, in the given order.
+    pub fn capabilities(&self, capabilities: &[(&str, Option<&str>)]) -> Result<String, Error> {
+        self.packet(&self.destination, &status::capabilities(capabilities)?)
+    }
+
+    /// IGate capabilities, the reply to `?IGATE?` queries: the number of messages
+    /// and of local stations the IGate has passed on.
+    pub fn igate_capabilities(&self, messages: u32, local_stations: u32) -> Result<String, Error> {
+        let (messages, local_stations) = (messages.to_string(), local_stations.to_string());
+        self.capabilities(&[("IGATE", None), ("MSG_CNT", Some(&messages)), ("LOC_CNT", Some(&local_stations))])
+    }
+
+    /// Message packets, one for each part of a long text. With `ids`, every part gets
+    /// the next message ID so that the addressee acknowledges it.
+    pub fn message(&self, message: &Message, ids: Option<&mut MessageIds>) -> Result<Vec<String>, Error> {
+        message.encode(ids)?.iter().map(|body| self.packet(&self.destination, body)).collect()
+    }
+
+    /// Acknowledgement of the message `id` received from `addressee`.
+    pub fn ack(&self, addressee: &str, id: &str) -> Result<String, Error> {
+        self.packet(&self.destination, &message::ack("ack", addressee, id)?)
+    }
+
+    /// Rejection of the message `id` received from `addressee`.
+    pub fn rej(&self, addressee: &str, id: &str) -> Result<String, Error> {
+        self.packet(&self.destination, &message::ack("rej", addressee, id)?)
+    }
+
+    /// Bulletin or announcement with up to 67 characters of text.
+    pub fn bulletin(&self, bulletin: &Bulletin, text: &str) -> Result<String, Error> {
+        self.packet(&self.destination, &bulletin.encode(text)?)
+    }
+
+    /// Third-party packet `}` carrying a packet from APRS-IS, the way IGates send it
+    /// on RF. The path of the carried packet is replaced with `TCPIP` and the builder's
+    /// source, marked as used.
+    pub fn third_party(&self, packet: &str) -> Result<String, Error> {
+        let colon = match packet.find(':') {
+            Some(colon) => colon,
+            None => return invalid(format!("no header in {:?}", packet)),
+        };
+        let (header, body) = (&packet[..colon], &packet[colon + 1..]);
+        let mut calls = header.splitn(2, '>');
+        let source = calls.next().unwrap_or("");
+        let destination = calls.next().and_then(|path| path.split(',').next()).unwrap_or("");
+        if source.is_empty() || destination.is_empty() {
+            return invalid(format!("no source or destination in {:?}", packet))
+        }
+        let body = format!("}}{}>{},TCPIP,{}*:{}", source, destination, self.source, body);
+        self.packet(&self.destination, &body)
+    }
+
+    pub(crate) fn source(&self) -> &str {
+        &self.source
+    }
+
+    // Puts a header in front of an encoded body, checking the callsigns
+    fn packet(&self, destination: &str, body: &str) -> Result<String, Error> {
+        check_call("source", &self.source)?;
+        check_call("destination", destination)?;
+        for digi in &self.path {
+            check_digi(digi)?;
+        }
+        check_text("packet", body)?;
+        let mut packet = format!("{}>{}", self.source, destination);
+        for digi in &self.path {
+            packet.push(',');
+            packet.push_str(digi);
+        }
+        packet.push(':');
+        packet.push_str(body);
+        Ok(packet)
+    }
+}
+
+/// Time of a report, always in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timestamp {
+    /// Day of month, hours and minutes, like `092345z`.
+    Dhm(SystemTime),
+    /// Hours, minutes and seconds, like `234517h`.
+    Hms(SystemTime),
+}
+
+impl Timestamp {
+    fn encode(&self) -> Result<String, Error> {
+        Ok(match *self {
+            Timestamp::Dhm(t) => {
+                let t = utc(t)?;
+                format!("{}{}{}z", &t[8..10], &t[11..13], &t[14..16])
+            },
+            Timestamp::Hms(t) => {
+                let t = utc(t)?;
+                format!("{}{}{}h", &t[11..13], &t[14..16], &t[17..19])
+            },
+        })
+    }
+}
+
+// `YYYY-MM-DDTHH:MM:SSZ`
+fn utc(t: SystemTime) -> Result<String, Error> {
+    // humantime panics outside of years 1970 to 9999
+    match t.duration_since(UNIX_EPOCH) {
+        Ok(d) if d.as_secs() < 253_402_300_800 => Ok(humantime::format_rfc3339_seconds(t).to_string()),
+        _ => invalid(format!("timestamp {:?} is not between 1970 and 9999", t)),
+    }
+}
+
+fn invalid<T>(msg: String) -> Result<T, Error> {
+    Err(Error::Encode(msg))
+}
+
+// Same rules as libfap for APRS-IS packets
+fn check_call(what: &str, call: &str) -> Result<(), Error> {
+    let valid = |c: char| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-';
+    if call.is_empty() || call.len() > 9 || !call.chars().all(valid) {
+        return invalid(format!("invalid {} callsign {:?}", what, call))
+    }
+    Ok(())
+}
+
+fn check_digi(digi: &str) -> Result<(), Error> {
+    let call = digi.strip_suffix('*').unwrap_or(digi);
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '-';
+    if call.is_empty() || call.len() > 9 || !call.chars().all(valid) {
+        return invalid(format!("invalid path element {:?}", digi))
+    }
+    Ok(())
+}
+
+// TNC2 packets end at CR or LF, and libfap rejects NULs
+fn check_text(what: &str, text: &str) -> Result<(), Error> {
+    if text.contains(['\r', '\n', '\0']) {
+        return invalid(format!("{} must not contain line breaks or NULs", what))
+    }
+    Ok(())
+}
+
+// Fixed-width base-91 number as used in compressed positions and telemetry
+fn base91(mut value: u32, width: usize) -> String {
+    let mut digits = vec![b'!'; width];
+    for digit in digits.iter_mut().rev() {
+        *digit = (value % 91) as u8 + 33;
+        value /= 91;
+    }
+    String::from_utf8(digits).unwrap()
+}
+
+// Uppercase base-36 digit
+fn digit36(d: u32) -> char {
+    ::std::char::from_digit(d, 36).unwrap().to_ascii_uppercase()
+}
+
+// Table is `/`, `\` or an overlay; the code is any printable character
+fn symbol_is_valid(table: char, code: char) -> bool {
+    (table == '/' || table == '\\' || table.is_ascii_uppercase() || table.is_ascii_digit())
+        && ('!'..='~').contains(&code)
+}
+
+// Altitude in meters as three base-91 digits, counted from 10 km below sea level
+fn altitude_base91(altitude: f64) -> Result<String, Error> {
+    let value = (altitude + 10000.0).round();
+    if !(0.0..=753570.0).contains(&value) {
+        return invalid(format!("invalid altitude {}", altitude))
+    }
+    Ok(base91(value as u32, 3))
+}
+
+// `|ssvv..|` comment telemetry; the bits only fit after all five values
+fn telemetry_base91(seq: u32, values: &[u32], bits: Option<u8>) -> Result<String, Error> {
+    if values.is_empty() || values.len() > 5 || (bits.is_some() && values.len() != 5) {
+        return invalid("comment telemetry takes one to five values, and bits only with five".to_string())
+    }
+    if seq > 8280 || values.iter().any(|&v| v > 8280) {
+        return invalid("comment telemetry numbers must not exceed 8280".to_string())
+    }
+    let mut out = format!("|{}", base91(seq, 2));
+    for &value in values {
+        out.push_str(&base91(value, 2));
+    }
+    if let Some(bits) = bits {
+        out.push_str(&base91(u32::from(bits), 2));
+    }
+    out.push('|');
+    Ok(out)
+}
@@ -0,0 +1,108 @@
+use super::{invalid, PositionReport, Timestamp};
+use Error;
+
+/// Object: a position report for something other than the sending station, like a
+/// checkpoint or a weather front, with its own name and timestamp.
+///
+/// Objects stay on the map until killed: send the same object once more with
+/// `killed()` when it goes away.
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::{ObjectReport, PacketBuilder, PositionReport, Timestamp};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let time = Timestamp::Dhm(UNIX_EPOCH + Duration::from_secs(1528597526));
+/// let position = PositionReport::new(49.0583, -72.0292).symbol('/', '+').comment("Aid station 2");
+/// let object = ObjectReport::new("AID2", time, position);
+/// let builder = PacketBuilder::new("N0CALL");
+/// assert_eq!(builder.object(&object).unwrap(),
+///            "N0CALL>APRS:;AID2     *100225z4903.50N/07201.75W+Aid station 2");
+/// assert_eq!(builder.object(&object.killed()).unwrap(),
+///            "N0CALL>APRS:;AID2     _100225z4903.50N/07201.75W+Aid station 2");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectReport {
+    name: String,
+    timestamp: Timestamp,
+    position: PositionReport,
+    alive: bool,
+}
+
+impl ObjectReport {
+    /// Live object named with up to 9 printable ASCII characters. The position
+    /// report must not have a timestamp of its own.
+    pub fn new(name: &str, timestamp: Timestamp, position: PositionReport) -> ObjectReport {
+        ObjectReport {
+            name: name.to_string(),
+            timestamp,
+            position,
+            alive: true,
+        }
+    }
+
+    /// Mark the object as killed, removing it from maps.
+    pub fn killed(mut self) -> ObjectReport {
+        self.alive = false;
+        self
+    }
+
+    pub(crate) fn encode(&self) -> Result<String, Error> {
+        let valid = |c: char| (' '..='~').contains(&c);
+        if self.name.trim().is_empty() || self.name.len() > 9 || !self.name.chars().all(valid) {
+            return invalid(format!("invalid object name {:?}", self.name))
+        }
+        if self.position.has_timestamp() {
+            return invalid("objects take their timestamp separately from the position".to_string())
+        }
+        Ok(format!(";{:9}{}{}{}", self.name, if self.alive { '*' } else { '_' },
+                   self.timestamp.encode()?, self.position.encode_position()?))
+    }
+}
+
+/// Item: like an object, but without timestamp and meant for things that do not
+/// change much, like a fixed aid station.
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::{ItemReport, PacketBuilder, PositionReport};
+///
+/// let item = ItemReport::new("CP1", PositionReport::new(49.0583, -72.0292).symbol('\\', 'm'));
+/// assert_eq!(PacketBuilder::new("N0CALL").item(&item).unwrap(),
+///            "N0CALL>APRS:)CP1!4903.50N\\07201.75Wm");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemReport {
+    name: String,
+    position: PositionReport,
+    alive: bool,
+}
+
+impl ItemReport {
+    /// Live item named with 3 to 9 printable ASCII characters other than `!` and `_`.
+    /// The position report must not have a timestamp.
+    pub fn new(name: &str, position: PositionReport) -> ItemReport {
+        ItemReport {
+            name: name.to_string(),
+            position,
+            alive: true,
+        }
+    }
+
+    /// Mark the item as killed, removing it from maps.
+    pub fn killed(mut self) -> ItemReport {
+        self.alive = false;
+        self
+    }
+
+    pub(crate) fn encode(&self) -> Result<String, Error> {
+        let valid = |c: char| (' '..='~').contains(&c) && c != '!' && c != '_';
+        if !(3..=9).contains(&self.name.len()) || !self.name.chars().all(valid) {
+            return invalid(format!("invalid item name {:?}", self.name))
+        }
+        if self.position.has_timestamp() {
+            return invalid("items have no timestamp".to_string())
+        }
+        Ok(format!("){}{}{}", self.name, if self.alive { '!' } else { '_' }, self.position.encode_position()?))
+    }
+}
@@ -0,0 +1,392 @@
+use super::{base91, check_text, invalid, symbol_is_valid, telemetry_base91, Timestamp};
+use Error;
+
+const KNOT_TO_KMH: f64 = 1.852;
+const MPH_TO_KMH: f64 = 1.609344;
+const FT_TO_M: f64 = 0.3048;
+
+/// Position report, in the same units `Packet` reports them.
+///
+/// Uncompressed reports carry one data extension: course and speed, PHG or range.
+/// Compressed ones keep course and speed, altitude or range in the compressed
+/// position itself, with PHG or range as an extension; altitude that does not fit
+/// is added to the comment as `/A=`.
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::{PacketBuilder, PositionReport};
+///
+/// let report = PositionReport::new(49.0583, -72.0292)
+///     .symbol('/', '>')
+///     .course_speed(88, 66.7)
+///     .altitude(234.7)
+///     .comment("Test");
+/// let raw = PacketBuilder::new("N0CALL").position(&report).unwrap();
+/// assert_eq!(raw, "N0CALL>APRS:!4903.50N/07201.75W>088/036/A=000770Test");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionReport {
+    latitude: f64,
+    longitude: f64,
+    symbol_table: char,
+    symbol_code: char,
+    messaging: bool,
+    timestamp: Option<Timestamp>,
+    compressed: bool,
+    ambiguity: u8,
+    course_speed: Option<(u32, f64)>,
+    altitude: Option<f64>,
+    range: Option<f64>,
+    phg: Option<Phg>,
+    dao: bool,
+    telemetry: Option<(u32, Vec<u32>, Option<u8>)>,
+    comment: String,
+}
+
+impl PositionReport {
+    /// Uncompressed report without timestamp or messaging, with the `/` symbol.
+    pub fn new(latitude: f64, longitude: f64) -> PositionReport {
+        PositionReport {
+            latitude,
+            longitude,
+            symbol_table: '/',
+            symbol_code: '/',
+            messaging: false,
+            timestamp: None,
+            compressed: false,
+            ambiguity: 0,
+            course_speed: None,
+            altitude: None,
+            range: None,
+            phg: None,
+            dao: false,
+            telemetry: None,
+            comment: String::new(),
+        }
+    }
+
+    /// Symbol table (`/`, `\` or an overlay character) and symbol code.
+    pub fn symbol(mut self, table: char, code: char) -> PositionReport {
+        self.symbol_table = table;
+        self.symbol_code = code;
+        self
+    }
+
+    /// Whether the station is capable of APRS messaging.
+    pub fn messaging(mut self, messaging: bool) -> PositionReport {
+        self.messaging = messaging;
+        self
+    }
+
+    /// Time of the report. Objects and items take theirs separately.
+    pub fn timestamp(mut self, timestamp: Timestamp) -> PositionReport {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Use base-91 compressed format.
+    pub fn compressed(mut self, compressed: bool) -> PositionReport {
+        self.compressed = compressed;
+        self
+    }
+
+    /// Number of position digits to blank out, 0 to 4. Uncompressed reports only.
+    pub fn ambiguity(mut self, ambiguity: u8) -> PositionReport {
+        self.ambiguity = ambiguity;
+        self
+    }
+
+    /// Course in degrees (1 to 360, 0 if unknown) and speed in km/h.
+    pub fn course_speed(mut self, course: u32, speed: f64) -> PositionReport {
+        self.course_speed = Some((course, speed));
+        self
+    }
+
+    /// Altitude in meters.
+    pub fn altitude(mut self, altitude: f64) -> PositionReport {
+        self.altitude = Some(altitude);
+        self
+    }
+
+    /// Radio range in km.
+    pub fn range(mut self, range: f64) -> PositionReport {
+        self.range = Some(range);
+        self
+    }
+
+    pub fn phg(mut self, phg: Phg) -> PositionReport {
+        self.phg = Some(phg);
+        self
+    }
+
+    /// Add a base-91 `!DAO!` extension for about 0.2 m resolution. Uncompressed reports only.
+    pub fn dao(mut self, dao: bool) -> PositionReport {
+        self.dao = dao;
+        self
+    }
+
+    /// Base-91 comment telemetry: sequence number, one to five values and optionally
+    /// eight bits (the first bit is the lowest one). All numbers go up to 8280.
+    pub fn telemetry(mut self, seq: u32, values: &[u32], bits: Option<u8>) -> PositionReport {
+        self.telemetry = Some((seq, values.to_vec(), bits));
+        self
+    }
+
+    pub fn comment(mut self, comment: &str) -> PositionReport {
+        self.comment = comment.to_string();
+        self
+    }
+
+    pub(crate) fn encode(&self) -> Result<String, Error> {
+        let mut body = String::new();
+        body.push(match (self.timestamp.is_some(), self.messaging) {
+            (false, false) => '!',
+            (false, true) => '=',
+            (true, false) => '/',
+            (true, true) => '@',
+        });
+        if let Some(ref timestamp) = self.timestamp {
+            body.push_str(&timestamp.encode()?);
+        }
+        body.push_str(&self.encode_position()?);
+        Ok(body)
+    }
+
+    // Complete weather report: the position with the weather station symbol, followed
+    // by the weather data where extensions and comment would go
+    pub(crate) fn encode_weather(&self, weather: &str) -> Result<String, Error> {
+        if self.compressed || self.course_speed.is_some() || self.altitude.is_some() || self.range.is_some()
+            || self.phg.is_some() || self.dao || self.telemetry.is_some() || !self.comment.is_empty() {
+            return invalid("weather reports need an uncompressed position without extensions or comment".to_string())
+        }
+        Ok(self.clone().symbol(self.symbol_table, '_').encode()? + weather)
+    }
+
+    pub(crate) fn coordinates(&self) -> (f64, f64) {
+        (self.latitude, self.longitude)
+    }
+
+    pub(crate) fn has_timestamp(&self) -> bool {
+        self.timestamp.is_some()
+    }
+
+    // Everything from the position on, as also used in objects and items
+    pub(crate) fn encode_position(&self) -> Result<String, Error> {
+        check_text("comment", &self.comment)?;
+        if !(-90.0..=90.0).contains(&self.latitude) || !(-180.0..=180.0).contains(&self.longitude) {
+            return invalid(format!("invalid position {}, {}", self.latitude, self.longitude))
+        }
+        if !symbol_is_valid(self.symbol_table, self.symbol_code) {
+            return invalid(format!("invalid symbol {}{}", self.symbol_table, self.symbol_code))
+        }
+        if self.ambiguity > 4 {
+            return invalid(format!("invalid position ambiguity {}", self.ambiguity))
+        }
+        if self.dao && self.ambiguity > 0 {
+            return invalid("!DAO! can not be used with position ambiguity".to_string())
+        }
+
+        let mut body = String::new();
+        let mut altitude = self.altitude;
+        let mut range = self.range;
+        let mut extensions = Vec::new();
+        let mut dao = None;
+        if self.compressed {
+            if self.ambiguity > 0 || self.dao {
+                return invalid("compressed positions have no ambiguity or !DAO!".to_string())
+            }
+            let (cs, used) = self.compressed_cs()?;
+            match used {
+                Compressed::Altitude => altitude = None,
+                Compressed::Range => range = None,
+                _ => {},
+            }
+            let table = match self.symbol_table {
+                c @ '0'..='9' => (c as u8 - b'0' + b'a') as char,
+                c => c,
+            };
+            body.push(table);
+            body.push_str(&base91((380926.0 * (90.0 - self.latitude)) as u32, 4));
+            body.push_str(&base91((190463.0 * (180.0 + self.longitude)) as u32, 4));
+            body.push(self.symbol_code);
+            body.push_str(&cs);
+        } else {
+            let lat = coordinate(self.latitude, 2, self.dao);
+            let lon = coordinate(self.longitude, 3, self.dao);
+            body.push_str(&blank_digits(&lat.digits, self.ambiguity));
+            body.push(if self.latitude < 0.0 { 'S' } else { 'N' });
+            body.push(self.symbol_table);
+            body.push_str(&blank_digits(&lon.digits, self.ambiguity));
+            body.push(if self.longitude < 0.0 { 'W' } else { 'E' });
+            body.push(self.symbol_code);
+            if self.dao {
+                dao = Some(format!("!w{}{}!", lat.dao, lon.dao));
+            }
+            if let Some((course, speed)) = self.course_speed {
+                if course > 360 {
+                    return invalid(format!("invalid course {}", course))
+                }
+                let knots = (speed / KNOT_TO_KMH).round();
+                if !(0.0..=999.0).contains(&knots) {
+                    return invalid(format!("invalid speed {}", speed))
+                }
+                extensions.push(format!("{:03}/{:03}", course, knots as u32));
+            }
+        }
+        if let Some(ref phg) = self.phg {
+            extensions.push(phg.encode()?);
+        }
+        if let Some(range) = range {
+            let miles = (range / MPH_TO_KMH).round();
+            if !(0.0..=9999.0).contains(&miles) {
+                return invalid(format!("invalid range {}", range))
+            }
+            extensions.push(format!("RNG{:04}", miles as u32));
+        }
+        if extensions.len() > 1 {
+            return invalid("only one of course and speed, PHG or range fits in a position report".to_string())
+        }
+        for extension in extensions {
+            body.push_str(&extension);
+        }
+
+        if let Some(altitude) = altitude {
+            body.push_str(&altitude_comment(altitude)?);
+        }
+        body.push_str(&self.comment);
+        if let Some((seq, ref values, bits)) = self.telemetry {
+            body.push_str(&telemetry_base91(seq, values, bits)?);
+        }
+        if let Some(dao) = dao {
+            body.push_str(&dao);
+        }
+        Ok(body)
+    }
+
+    // The csT bytes of a compressed position, and what went into them
+    fn compressed_cs(&self) -> Result<(String, Compressed), Error> {
+        if let Some((course, speed)) = self.course_speed {
+            if course > 360 {
+                return invalid(format!("invalid course {}", course))
+            }
+            let s = (speed / KNOT_TO_KMH + 1.0).ln() / 1.08f64.ln();
+            if !(0.0..=90.0).contains(&s) {
+                return invalid(format!("invalid speed {}", speed))
+            }
+            let c = ((course as f64 / 4.0).round() as u32) % 90;
+            let cs = format!("{}{}{}", (c as u8 + 33) as char, (s.round() as u8 + 33) as char, compression_type(3));
+            return Ok((cs, Compressed::CourseSpeed))
+        }
+        if let Some(altitude) = self.altitude {
+            let cs = (altitude / FT_TO_M).ln() / 1.002f64.ln();
+            if (0.0..=8280.0).contains(&cs) {
+                return Ok((format!("{}{}", base91(cs.round() as u32, 2), compression_type(2)), Compressed::Altitude))
+            }
+        }
+        if let Some(range) = self.range {
+            let s = (range / MPH_TO_KMH / 2.0).ln() / 1.08f64.ln();
+            if s.is_nan() || s > 90.0 {
+                return invalid(format!("invalid range {}", range))
+            }
+            let s = if s > 0.0 { s.round() as u8 } else { 0 };
+            return Ok((format!("{{{}{}", (s + 33) as char, compression_type(0)), Compressed::Range))
+        }
+        Ok((" sT".to_string(), Compressed::Nothing))
+    }
+}
+
+/// Power, effective antenna height, gain and directivity of a station.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Phg {
+    /// Transmitter power in W.
+    pub power: f64,
+    /// Antenna height above average terrain in m.
+    pub height: f64,
+    /// Antenna gain in dB.
+    pub gain: f64,
+    /// Direction of the antenna's maximum gain in degrees, `None` for omnidirectional.
+    pub directivity: Option<u32>,
+}
+
+impl Phg {
+    pub(crate) fn encode(&self) -> Result<String, Error> {
+        let p = self.power.sqrt().round();
+        let h = (self.height / FT_TO_M / 10.0).log2().round().max(0.0);
+        let g = self.gain.round();
+        if !(0.0..=9.0).contains(&p) || !(0.0..=78.0).contains(&h) || !(0.0..=9.0).contains(&g) {
+            return invalid(format!("PHG values out of range: {:?}", self))
+        }
+        let d = match self.directivity {
+            None => 0,
+            Some(d) => match ((d % 360) as f64 / 45.0).round() as u32 {
+                0 => 8,
+                d => d,
+            },
+        };
+        Ok(format!("PHG{}{}{}{}", p as u32, (h as u8 + b'0') as char, g as u32, d))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Compressed {
+    Nothing,
+    CourseSpeed,
+    Altitude,
+    Range,
+}
+
+// Compression type byte: current GPS fix, software origin and the NMEA source,
+// which tells altitude (GGA) from course and speed
+fn compression_type(nmea_source: u8) -> char {
+    ((0x20 | nmea_source << 3 | 0x02) + 33) as char
+}
+
+struct Coordinate {
+    // DDMM.mm or DDDMM.mm
+    digits: String,
+    // base-91 !DAO! digit
+    dao: char,
+}
+
+fn coordinate(value: f64, degree_digits: usize, dao: bool) -> Coordinate {
+    // in 1/91 of 1/100 of a minute for !DAO!, 1/100 of a minute otherwise
+    let (hundredths, extra) = if dao {
+        let units = (value.abs() * 6000.0 * 91.0).round() as u64;
+        (units / 91, units % 91)
+    } else {
+        ((value.abs() * 6000.0).round() as u64, 0)
+    };
+    let minutes = hundredths % 6000;
+    Coordinate {
+        digits: format!("{:0width$}{:02}.{:02}", hundredths / 6000, minutes / 100, minutes % 100, width = degree_digits),
+        dao: (extra as u8 + 33) as char,
+    }
+}
+
+// Position ambiguity blanks digits from the right, skipping the decimal point
+fn blank_digits(digits: &str, ambiguity: u8) -> String {
+    let mut blank = ambiguity as usize;
+    let mut out: Vec<char> = digits.chars().collect();
+    for c in out.iter_mut().rev() {
+        if blank == 0 {
+            break
+        }
+        if *c != '.' {
+            *c = ' ';
+            blank -= 1;
+        }
+    }
+    out.into_iter().collect()
+}
+
+fn altitude_comment(altitude: f64) -> Result<String, Error> {
+    let feet = (altitude / FT_TO_M).round();
+    if !(-99999.0..=999999.0).contains(&feet) {
+        return invalid(format!("invalid altitude {}", altitude))
+    }
+    if feet < 0.0 {
+        Ok(format!("/A=-{:05}", -feet as u32))
+    } else {
+        Ok(format!("/A={:06}", feet as u32))
+    }
+}
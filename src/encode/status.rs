@@ -0,0 +1,123 @@
+use std::time::SystemTime;
+use super::{check_text, digit36, invalid, symbol_is_valid, Timestamp};
+use Error;
+
+/// Status report `>`, with text and optional timestamp, Maidenhead locator and
+/// beam heading.
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::{PacketBuilder, StatusReport};
+///
+/// let status = StatusReport::new("Net control tonight").grid("IO91SX", '/', 'G').beam(110, 490.0);
+/// assert_eq!(PacketBuilder::new("N0CALL").status(&status).unwrap(),
+///            "N0CALL>APRS:>IO91SX/G Net control tonight^B7");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusReport {
+    text: String,
+    timestamp: Option<SystemTime>,
+    grid: Option<(String, char, char)>,
+    beam: Option<(u32, f64)>,
+}
+
+impl StatusReport {
+    pub fn new(text: &str) -> StatusReport {
+        StatusReport {
+            text: text.to_string(),
+            timestamp: None,
+            grid: None,
+            beam: None,
+        }
+    }
+
+    /// Time of the report, sent as day, hours and minutes in UTC. Can not be combined
+    /// with a locator.
+    pub fn timestamp(mut self, time: SystemTime) -> StatusReport {
+        self.timestamp = Some(time);
+        self
+    }
+
+    /// Maidenhead locator of 4 or 6 characters like `IO91SX`, with the symbol to show
+    /// the station with.
+    pub fn grid(mut self, locator: &str, symbol_table: char, symbol_code: char) -> StatusReport {
+        self.grid = Some((locator.to_string(), symbol_table, symbol_code));
+        self
+    }
+
+    /// Beam heading in degrees and effective radiated power in W, rounded to 10 degrees
+    /// and the nearest power of the `^HP` suffix.
+    pub fn beam(mut self, heading: u32, erp: f64) -> StatusReport {
+        self.beam = Some((heading, erp));
+        self
+    }
+
+    pub(crate) fn encode(&self) -> Result<String, Error> {
+        check_text("status", &self.text)?;
+        if self.text.contains(['|', '~']) {
+            return invalid("status text must not contain '|' or '~'".to_string())
+        }
+        let mut status = String::new();
+        if let Some(time) = self.timestamp {
+            if self.grid.is_some() {
+                return invalid("status reports with a locator have no timestamp".to_string())
+            }
+            status.push_str(&Timestamp::Dhm(time).encode()?);
+        }
+        if let Some((ref locator, table, code)) = self.grid {
+            if !is_locator(locator) || !symbol_is_valid(table, code) {
+                return invalid(format!("invalid locator {} or symbol {}{}", locator, table, code))
+            }
+            status.push_str(&format!("{}{}{} ", locator, table, code));
+        }
+        status.push_str(&self.text);
+        if let Some((heading, erp)) = self.beam {
+            let h = (heading % 360 + 5) / 10 % 36;
+            let p = (erp / 10.0).sqrt().round();
+            if !(0.0..36.0).contains(&p) {
+                return invalid(format!("invalid effective radiated power {}", erp))
+            }
+            status.push('^');
+            status.push(digit36(h));
+            status.push(digit36(p as u32));
+        }
+        // the limit leaves 55 characters of text after a timestamp
+        if status.chars().count() > 62 {
+            return invalid("status reports are limited to 62 characters".to_string())
+        }
+        Ok(format!(">{}", status))
+    }
+}
+
+// Field, square and optionally subsquare: `IO91` or `IO91SX`
+fn is_locator(locator: &str) -> bool {
+    let b = locator.as_bytes();
+    (b.len() == 4 || b.len() == 6)
+        && b[..2].iter().all(|c| (b'A'..=b'R').contains(c))
+        && b[2..4].iter().all(|c| c.is_ascii_digit())
+        && b[4..].iter().all(|c| (b'A'..=b'X').contains(&c.to_ascii_uppercase()))
+}
+
+// `<CAP,CAP=value,...`
+pub(crate) fn capabilities(capabilities: &[(&str, Option<&str>)]) -> Result<String, Error> {
+    if capabilities.is_empty() {
+        return invalid("no capabilities given".to_string())
+    }
+    let mut body = String::from("<");
+    for (i, &(name, value)) in capabilities.iter().enumerate() {
+        check_text("capability", name)?;
+        if name.is_empty() || name.contains([',', '=']) || matches!(value, Some(v) if v.contains(',')) {
+            return invalid(format!("invalid capability {:?}={:?}", name, value))
+        }
+        if i > 0 {
+            body.push(',');
+        }
+        body.push_str(name);
+        if let Some(value) = value {
+            check_text("capability", value)?;
+            body.push('=');
+            body.push_str(value);
+        }
+    }
+    Ok(body)
+}
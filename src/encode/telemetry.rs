@@ -0,0 +1,211 @@
+use super::{check_text, invalid};
+use super::message::{addressee, check_message_text, MAX_MESSAGE_LEN};
+use Error;
+
+/// `T#` telemetry report: sequence number, five analog values and eight bits.
+///
+/// Values that are whole numbers from 0 to 999 are sent with three digits as in the
+/// original specification, others as short decimals. Missing values are left empty.
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::{PacketBuilder, TelemetryReport};
+///
+/// let report = TelemetryReport::new(5, &[13.8, 120.0, 3.0]).bits(0b0000_0101);
+/// assert_eq!(PacketBuilder::new("N0CALL").telemetry(&report).unwrap(),
+///            "N0CALL>APRS:T#005,13.8,120,003,,,10100000");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelemetryReport {
+    seq: u32,
+    values: Vec<f64>,
+    bits: u8,
+    comment: String,
+}
+
+impl TelemetryReport {
+    /// Report with sequence number 0 to 999 and up to five analog values.
+    pub fn new(seq: u32, values: &[f64]) -> TelemetryReport {
+        TelemetryReport {
+            seq,
+            values: values.to_vec(),
+            bits: 0,
+            comment: String::new(),
+        }
+    }
+
+    /// Digital bits, the lowest one first in the packet.
+    pub fn bits(mut self, bits: u8) -> TelemetryReport {
+        self.bits = bits;
+        self
+    }
+
+    pub fn comment(mut self, comment: &str) -> TelemetryReport {
+        self.comment = comment.to_string();
+        self
+    }
+
+    pub(crate) fn encode(&self) -> Result<String, Error> {
+        check_text("comment", &self.comment)?;
+        if self.seq > 999 {
+            return invalid(format!("invalid telemetry sequence number {}", self.seq))
+        }
+        if self.values.len() > 5 {
+            return invalid(format!("{} telemetry values, at most 5 fit", self.values.len()))
+        }
+        let mut body = format!("T#{:03}", self.seq);
+        for i in 0..5 {
+            body.push(',');
+            if let Some(&value) = self.values.get(i) {
+                body.push_str(&analog(value)?);
+            }
+        }
+        body.push(',');
+        body.push_str(&bit_string(self.bits));
+        body.push_str(&self.comment);
+        Ok(body)
+    }
+}
+
+// Three digits where possible, the few decimals needed otherwise
+fn analog(value: f64) -> Result<String, Error> {
+    let text = if value.fract() == 0.0 && (0.0..=999.0).contains(&value) {
+        format!("{:03}", value as u32)
+    } else {
+        format!("{}", value)
+    };
+    let digits = text.trim_start_matches('-');
+    if !value.is_finite() || digits.len() > 8 || (!digits.contains('.') && digits.len() > 6) {
+        return invalid(format!("telemetry value {} does not fit, round it to fewer digits", value))
+    }
+    Ok(text)
+}
+
+// `1` or `0` for each bit, the lowest first
+fn bit_string(bits: u8) -> String {
+    (0..8).map(|bit| if bits >> bit & 1 == 1 { '1' } else { '0' }).collect()
+}
+
+/// Telemetry metadata, sent as a message from the station to itself, which tells
+/// receivers how to label and scale the `T#` values.
+///
+/// Names and units have the length limits of the APRS specification: 7, 7, 6, 6 and 5
+/// characters for the analog channels, then 6, 5, 4, 4, 4, 3, 3 and 3 for the bits.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TelemetryDefinition {
+    /// `PARM.`: names of up to 5 analog channels and 8 bits.
+    Parameters(Vec<String>),
+    /// `UNIT.`: units or labels of up to 5 analog channels and 8 bits.
+    Units(Vec<String>),
+    /// `EQNS.`: coefficients `a`, `b` and `c` of `a * v² + b * v + c` for up to 5 analog channels.
+    Equations(Vec<(f64, f64, f64)>),
+    /// `BITS.`: the value of each bit that means "on", the lowest first, and a project
+    /// title of up to 23 characters.
+    Bits(u8, String),
+}
+
+const NAME_LIMITS: [usize; 13] = [7, 7, 6, 6, 5, 6, 5, 4, 4, 4, 3, 3, 3];
+
+impl TelemetryDefinition {
+    /// Reads the text of a telemetry metadata message, e.g. `PARM.Vbat,Vsol`, as
+    /// `Packet::message()` gives it. libfap recognizes these messages but leaves them
+    /// undecoded.
+    ///
+    /// Empty names are kept, so that the following ones stay on their channel. Missing
+    /// coefficients default to `0,1,0`, which leaves values as they are, and missing
+    /// bit senses to 1.
+    ///
+    /// ```rust
+    /// extern crate fap;
+    /// use fap::TelemetryDefinition;
+    ///
+    /// assert_eq!(TelemetryDefinition::parse("EQNS.0,0.1,0,,,-40"),
+    ///            Some(TelemetryDefinition::Equations(vec![(0.0, 0.1, 0.0), (0.0, 1.0, -40.0)])));
+    /// assert_eq!(TelemetryDefinition::parse("BITS.10,Weather station"),
+    ///            Some(TelemetryDefinition::Bits(0b1111_1101, "Weather station".to_string())));
+    /// ```
+    pub fn parse(text: &str) -> Option<TelemetryDefinition> {
+        let text = text.trim_end();
+        let (kind, rest) = (text.get(..5)?, &text[5..]);
+        let names = || rest.split(',').map(|n| n.trim().to_string()).collect();
+        match kind {
+            "PARM." => Some(TelemetryDefinition::Parameters(names())),
+            "UNIT." => Some(TelemetryDefinition::Units(names())),
+            "EQNS." => {
+                let mut coefficients = Vec::new();
+                for field in rest.split(',').map(str::trim) {
+                    coefficients.push(if field.is_empty() { None } else { Some(field.parse::<f64>().ok()?) });
+                }
+                if coefficients.len() > 15 {
+                    return None
+                }
+                let equations = coefficients.chunks(3).map(|abc| {
+                    let x = |i: usize, default| abc.get(i).cloned().unwrap_or(None).unwrap_or(default);
+                    (x(0, 0.0), x(1, 1.0), x(2, 0.0))
+                }).collect();
+                Some(TelemetryDefinition::Equations(equations))
+            },
+            "BITS." => {
+                let (bits, title) = match rest.find(',') {
+                    Some(comma) => (&rest[..comma], rest[comma + 1..].trim()),
+                    None => (rest, ""),
+                };
+                let mut sense = 0xff;
+                for (i, bit) in bits.chars().enumerate() {
+                    match bit {
+                        '0' if i < 8 => sense &= !(1 << i),
+                        '1' if i < 8 => {},
+                        _ => return None,
+                    }
+                }
+                Some(TelemetryDefinition::Bits(sense, title.to_string()))
+            },
+            _ => None,
+        }
+    }
+
+    pub(crate) fn encode(&self, station: &str) -> Result<String, Error> {
+        let text = match *self {
+            TelemetryDefinition::Parameters(ref names) => format!("PARM.{}", names_list(names)?),
+            TelemetryDefinition::Units(ref units) => format!("UNIT.{}", names_list(units)?),
+            TelemetryDefinition::Equations(ref equations) => {
+                if equations.is_empty() || equations.len() > 5 {
+                    return invalid(format!("{} telemetry equations, 1 to 5 fit", equations.len()))
+                }
+                let mut coefficients = Vec::new();
+                for &(a, b, c) in equations {
+                    for &x in &[a, b, c] {
+                        if !x.is_finite() {
+                            return invalid(format!("invalid telemetry coefficient {}", x))
+                        }
+                        coefficients.push(x.to_string());
+                    }
+                }
+                format!("EQNS.{}", coefficients.join(","))
+            },
+            TelemetryDefinition::Bits(sense, ref title) => {
+                if title.chars().count() > 23 {
+                    return invalid(format!("telemetry project title {:?} is longer than 23 characters", title))
+                }
+                format!("BITS.{},{}", bit_string(sense), title)
+            },
+        };
+        check_message_text(&text)?;
+        if text.chars().count() > MAX_MESSAGE_LEN {
+            return invalid(format!("telemetry definition is longer than {} characters", MAX_MESSAGE_LEN))
+        }
+        Ok(format!(":{}:{}", addressee(station)?, text))
+    }
+}
+
+fn names_list(names: &[String]) -> Result<String, Error> {
+    if names.is_empty() || names.len() > NAME_LIMITS.len() {
+        return invalid(format!("{} telemetry names, 1 to 13 fit", names.len()))
+    }
+    for (name, &limit) in names.iter().zip(&NAME_LIMITS) {
+        if name.chars().count() > limit || name.contains(',') {
+            return invalid(format!("telemetry name {:?} is longer than {} characters or has a comma", name, limit))
+        }
+    }
+    Ok(names.join(","))
+}
@@ -0,0 +1,210 @@
+use std::time::SystemTime;
+use super::{check_text, invalid, utc, PositionReport};
+use Error;
+
+const MPH_TO_MS: f64 = 1.609344 / 3.6;
+const HINCH_TO_MM: f64 = 0.254;
+
+/// Weather report, in the same SI units `Weather` reports them.
+///
+/// Positionless reports carry the time of the measurement. Complete reports carry
+/// a position instead, shown with the weather station symbol. Values are rounded to
+/// the units of the packet format: mph, degrees Fahrenheit and hundredths of an inch.
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::{PacketBuilder, PositionReport, WeatherReport};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let time = UNIX_EPOCH + Duration::from_secs(1528597526);
+/// let report = WeatherReport::positionless(time).wind(220, 4.0).temperature(-5.0).humidity(87);
+/// let builder = PacketBuilder::new("N0CALL");
+/// assert_eq!(builder.weather(&report).unwrap(), "N0CALL>APRS:_06100225c220s009g...t023h87");
+///
+/// let report = WeatherReport::with_position(PositionReport::new(49.0583, -72.0292))
+///     .temperature(21.0)
+///     .pressure(1013.2);
+/// assert_eq!(builder.weather(&report).unwrap(), "N0CALL>APRS:!4903.50N/07201.75W_.../...g...t070b10132");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeatherReport {
+    location: Location,
+    wind_dir: Option<u32>,
+    wind_speed: Option<f64>,
+    wind_gust: Option<f64>,
+    temp: Option<f64>,
+    rain_1h: Option<f64>,
+    rain_24h: Option<f64>,
+    rain_midnight: Option<f64>,
+    humidity: Option<u32>,
+    pressure: Option<f64>,
+    luminosity: Option<u32>,
+    comment: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Location {
+    Positionless(SystemTime),
+    Position(PositionReport),
+}
+
+impl WeatherReport {
+    /// Positionless report `_MMDDHHMM...` for measurements taken at `time`.
+    pub fn positionless(time: SystemTime) -> WeatherReport {
+        WeatherReport::new(Location::Positionless(time))
+    }
+
+    /// Complete report with an uncompressed position without extensions or comment.
+    /// The symbol code is replaced with `_`; the position's timestamp, if any, is used.
+    pub fn with_position(position: PositionReport) -> WeatherReport {
+        WeatherReport::new(Location::Position(position))
+    }
+
+    fn new(location: Location) -> WeatherReport {
+        WeatherReport {
+            location,
+            wind_dir: None,
+            wind_speed: None,
+            wind_gust: None,
+            temp: None,
+            rain_1h: None,
+            rain_24h: None,
+            rain_midnight: None,
+            humidity: None,
+            pressure: None,
+            luminosity: None,
+            comment: String::new(),
+        }
+    }
+
+    /// Wind direction in degrees and sustained wind speed in m/s.
+    pub fn wind(mut self, direction: u32, speed: f64) -> WeatherReport {
+        self.wind_dir = Some(direction);
+        self.wind_speed = Some(speed);
+        self
+    }
+
+    /// Peak wind speed in m/s.
+    pub fn gust(mut self, gust: f64) -> WeatherReport {
+        self.wind_gust = Some(gust);
+        self
+    }
+
+    /// Temperature in degrees Celsius.
+    pub fn temperature(mut self, temp: f64) -> WeatherReport {
+        self.temp = Some(temp);
+        self
+    }
+
+    /// Rain in the last hour, in mm.
+    pub fn rain_1h(mut self, rain: f64) -> WeatherReport {
+        self.rain_1h = Some(rain);
+        self
+    }
+
+    /// Rain in the last 24 hours, in mm.
+    pub fn rain_24h(mut self, rain: f64) -> WeatherReport {
+        self.rain_24h = Some(rain);
+        self
+    }
+
+    /// Rain since local midnight, in mm.
+    pub fn rain_midnight(mut self, rain: f64) -> WeatherReport {
+        self.rain_midnight = Some(rain);
+        self
+    }
+
+    /// Relative humidity, 1 to 100 %.
+    pub fn humidity(mut self, humidity: u32) -> WeatherReport {
+        self.humidity = Some(humidity);
+        self
+    }
+
+    /// Barometric pressure in millibars (hPa).
+    pub fn pressure(mut self, pressure: f64) -> WeatherReport {
+        self.pressure = Some(pressure);
+        self
+    }
+
+    /// Luminosity in W/m², up to 1999.
+    pub fn luminosity(mut self, luminosity: u32) -> WeatherReport {
+        self.luminosity = Some(luminosity);
+        self
+    }
+
+    /// Software and station type like `eMB31`, or free text.
+    pub fn comment(mut self, comment: &str) -> WeatherReport {
+        self.comment = comment.to_string();
+        self
+    }
+
+    pub(crate) fn encode(&self) -> Result<String, Error> {
+        check_text("comment", &self.comment)?;
+        let wind_dir = match self.wind_dir {
+            Some(dir) if dir > 360 => return invalid(format!("invalid wind direction {}", dir)),
+            dir => field(dir.map(f64::from), 3)?,
+        };
+        let wind_speed = field(converted("wind speed", self.wind_speed, MPH_TO_MS)?, 3)?;
+        let wind_gust = field(converted("wind gust", self.wind_gust, MPH_TO_MS)?, 3)?;
+        // Fahrenheit, with two digits after the minus sign
+        let temp = match self.temp.map(|t| (t * 1.8 + 32.0).round()) {
+            Some(f) if (-99.0..0.0).contains(&f) => format!("-{:02}", -f as u32),
+            Some(f) if (0.0..=999.0).contains(&f) => format!("{:03}", f as u32),
+            Some(_) => return invalid(format!("invalid temperature {:?}", self.temp)),
+            None => "...".to_string(),
+        };
+
+        let mut body = match self.location {
+            Location::Positionless(time) => {
+                let t = utc(time)?;
+                format!("_{}{}{}{}c{}s{}g{}t{}", &t[5..7], &t[8..10], &t[11..13], &t[14..16],
+                        wind_dir, wind_speed, wind_gust, temp)
+            },
+            Location::Position(ref position) => {
+                position.encode_weather(&format!("{}/{}g{}t{}", wind_dir, wind_speed, wind_gust, temp))?
+            },
+        };
+        for &(name, rain) in &[('r', self.rain_1h), ('p', self.rain_24h), ('P', self.rain_midnight)] {
+            if let Some(hinch) = converted("rain", rain, HINCH_TO_MM)? {
+                body.push(name);
+                body.push_str(&field(Some(hinch), 3)?);
+            }
+        }
+        match self.humidity {
+            Some(100) => body.push_str("h00"),
+            Some(h @ 1..=99) => body.push_str(&format!("h{:02}", h)),
+            Some(h) => return invalid(format!("invalid humidity {}", h)),
+            None => {},
+        }
+        if let Some(tenths) = converted("pressure", self.pressure, 0.1)? {
+            body.push('b');
+            body.push_str(&field(Some(tenths), 5)?);
+        }
+        match self.luminosity {
+            Some(l @ 0..=999) => body.push_str(&format!("L{:03}", l)),
+            Some(l @ 1000..=1999) => body.push_str(&format!("l{:03}", l - 1000)),
+            Some(l) => return invalid(format!("invalid luminosity {}", l)),
+            None => {},
+        }
+        body.push_str(&self.comment);
+        Ok(body)
+    }
+}
+
+// Value in the packet's unit, rounded
+fn converted(what: &str, value: Option<f64>, unit: f64) -> Result<Option<f64>, Error> {
+    match value {
+        Some(v) if v.is_finite() && v >= 0.0 => Ok(Some((v / unit).round())),
+        Some(v) => invalid(format!("invalid {} {}", what, v)),
+        None => Ok(None),
+    }
+}
+
+// Zero-padded field, dots if unknown
+fn field(value: Option<f64>, width: usize) -> Result<String, Error> {
+    match value {
+        None => Ok(".".repeat(width)),
+        Some(v) if v < 10f64.powi(width as i32) => Ok(format!("{:0width$}", v as u32, width = width)),
+        Some(v) => invalid(format!("weather value {} does not fit in {} digits", v, width)),
+    }
+}
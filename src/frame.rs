@@ -0,0 +1,69 @@
+//! Conversions between TNC-2 text frames and binary KISS or AX.25 frames.
+
+use bind::*;
+use std::os::raw::{c_char, c_uint};
+use {init, Error};
+
+// libfap refuses frames of FRAME_MAXLEN (512) bytes and more; its own conversion
+// buffers are twice that, and KISS byte stuffing may double the size again
+const FRAME_MAXLEN: usize = 512;
+const BUF_LEN: usize = 4 * FRAME_MAXLEN + 2;
+
+// libfap reports errors as a NUL-terminated message in the output buffer. It may also
+// fail without one, or leave a partial frame there, so anything without a NUL is ignored.
+fn conversion_error(buf: &[u8]) -> Error {
+    match buf.iter().position(|b| *b == 0) {
+        Some(end) if end > 0 => Error::Other(String::from_utf8_lossy(&buf[..end]).into_owned()),
+        _ => Error::Other("libfap could not convert the frame".to_string()),
+    }
+}
+
+fn convert<F>(f: F) -> Result<Vec<u8>, Error>
+    where F: FnOnce(*mut c_char, *mut c_uint) -> i32
+{
+    init();
+    let mut buf = vec![0u8; BUF_LEN];
+    let mut len = BUF_LEN as c_uint;
+    let ok = f(buf.as_mut_ptr() as *mut c_char, &mut len);
+    let len = (len as usize).min(BUF_LEN);
+    if ok == 0 {
+        return Err(conversion_error(&buf[..len]))
+    }
+    buf.truncate(len);
+    Ok(buf)
+}
+
+/// Converts a KISS data frame into a TNC-2 frame, returning it with the KISS command byte
+/// (TNC port and command, usually zero).
+///
+/// The frame may or may not start with FEND and must not be unstuffed. Only APRS UI
+/// frames are accepted.
+pub fn kiss_to_tnc2(frame: &[u8]) -> Result<(Vec<u8>, u8), Error> {
+    let mut tnc_id: c_uint = 0;
+    let tnc2 = convert(|out, out_len| unsafe {
+        fap_kiss_to_tnc2(frame.as_ptr() as *const c_char, frame.len() as c_uint, out, out_len, &mut tnc_id)
+    })?;
+    Ok((tnc2, tnc_id as u8))
+}
+
+/// Converts a TNC-2 frame into a complete KISS data frame, with FENDs on both ends and
+/// byte stuffing done.
+pub fn tnc2_to_kiss(frame: &[u8], tnc_id: u8) -> Result<Vec<u8>, Error> {
+    convert(|out, out_len| unsafe {
+        fap_tnc2_to_kiss(frame.as_ptr() as *const c_char, frame.len() as c_uint, c_uint::from(tnc_id), out, out_len)
+    })
+}
+
+/// Converts a raw AX.25 frame into a TNC-2 frame. Only APRS UI frames are accepted.
+pub fn ax25_to_tnc2(frame: &[u8]) -> Result<Vec<u8>, Error> {
+    convert(|out, out_len| unsafe {
+        fap_ax25_to_tnc2(frame.as_ptr() as *const c_char, frame.len() as c_uint, out, out_len)
+    })
+}
+
+/// Converts a TNC-2 frame into a raw AX.25 UI frame.
+pub fn tnc2_to_ax25(frame: &[u8]) -> Result<Vec<u8>, Error> {
+    convert(|out, out_len| unsafe {
+        fap_tnc2_to_ax25(frame.as_ptr() as *const c_char, frame.len() as c_uint, out, out_len)
+    })
+}
@@ -0,0 +1,230 @@
+//! Gating between RF and APRS-IS.
+//!
+//! `Igate` decides which packets heard on RF go to APRS-IS, adding the q construct,
+//! and which messages from APRS-IS go to RF, encapsulated as third-party packets. It
+//! keeps track of the stations heard on RF and of recently gated packets, but does no
+//! I/O: packets are passed in with the time they were received, and the lines to send
+//! are returned.
+//!
+//! ```rust
+//! extern crate fap;
+//! use fap::PacketBuilder;
+//! use fap::igate::Igate;
+//! use std::time::{Duration, UNIX_EPOCH};
+//!
+//! let mut igate = Igate::new(PacketBuilder::new("N0CALL-10").path(&["WIDE1-1"]));
+//! let now = UNIX_EPOCH + Duration::from_secs(1_500_000_000);
+//! assert_eq!(igate.rf_to_is("N1CALL-7>APRS,WIDE1-1*:!4903.50N/07201.75W>", now).as_deref(),
+//!            Some("N1CALL-7>APRS,WIDE1-1*,qAR,N0CALL-10:!4903.50N/07201.75W>"));
+//! // heard again through another digipeater
+//! assert_eq!(igate.rf_to_is("N1CALL-7>APRS,DIGI*:!4903.50N/07201.75W>", now), None);
+//!
+//! let message = "N2CALL>APRS,TCPIP*,qAC,T2TEST::N1CALL-7 :Hello{1";
+//! assert_eq!(igate.is_to_rf(message, now).as_deref(),
+//!            Some("N0CALL-10>APRS,WIDE1-1:}N2CALL>APRS,TCPIP,N0CALL-10*::N1CALL-7 :Hello{1"));
+//! ```
+
+use aprs::Packet as AprsPacket;
+use aprsis::{q_algorithm, Entry};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+use {ax25_to_tnc2, kiss_to_tnc2, DupeChecker, Error, Packet, PacketBuilder, PacketType, QueryResponder};
+
+/// RF to APRS-IS and APRS-IS to RF gating for one IGate station.
+#[derive(Debug, Clone)]
+pub struct Igate {
+    builder: PacketBuilder,
+    verified: bool,
+    local_window: Duration,
+    gated_to_is: DupeChecker,
+    gated_to_rf: DupeChecker,
+    heard: HashMap<String, SystemTime>,
+    // senders of messages gated to RF, whose next position follows them
+    senders: HashMap<String, SystemTime>,
+    messages: u32,
+    // latest time passed in, at which `?IGATE?` counts local stations
+    last_update: Option<SystemTime>,
+}
+
+impl Igate {
+    /// IGate with a verified APRS-IS login. `builder` gives the callsign, and the
+    /// destination and path of the packets sent on RF.
+    pub fn new(builder: PacketBuilder) -> Igate {
+        Igate {
+            builder,
+            verified: true,
+            local_window: Duration::from_secs(30 * 60),
+            gated_to_is: DupeChecker::new(Duration::from_secs(30)),
+            gated_to_rf: DupeChecker::new(Duration::from_secs(30)),
+            heard: HashMap::new(),
+            senders: HashMap::new(),
+            messages: 0,
+            last_update: None,
+        }
+    }
+
+    /// Whether the APRS-IS login has a passcode. Receive-only IGates mark packets
+    /// with `qAO` and never gate to RF.
+    pub fn verified(mut self, verified: bool) -> Igate {
+        self.verified = verified;
+        self
+    }
+
+    /// How long the same packet is not gated again, 30 seconds by default.
+    pub fn dupe_window(mut self, window: Duration) -> Igate {
+        self.gated_to_is = DupeChecker::new(window);
+        self.gated_to_rf = DupeChecker::new(window);
+        self
+    }
+
+    /// How long a station heard on RF counts as local, 30 minutes by default.
+    pub fn local_window(mut self, window: Duration) -> Igate {
+        self.local_window = window;
+        self
+    }
+
+    /// Gates a packet heard on RF, in TNC2 format. Returns the line to send to
+    /// APRS-IS, or `None` if the packet must not be gated.
+    ///
+    /// Packets with `NOGATE`, `RFONLY`, `TCPIP` or `TCPXX` in the path, generic
+    /// queries and packets already gated within the dupe window are dropped.
+    /// Third-party packets are gated without the outer header, unless they came from
+    /// APRS-IS.
+    pub fn rf_to_is(&mut self, packet: &str, now: SystemTime) -> Option<String> {
+        let packet = packet.trim_end_matches(['\r', '\n']);
+        let (source, _, path, body) = split(packet)?;
+        self.expire(now);
+        self.heard.insert(source.to_ascii_uppercase(), now);
+        if path.iter().any(|p| no_gate(p)) {
+            return None
+        }
+        let packet = match body.strip_prefix('}') {
+            Some(inner) => {
+                let (_, _, inner_path, _) = split(inner)?;
+                if inner_path.iter().any(|p| no_gate(p)) {
+                    return None
+                }
+                inner
+            },
+            None => packet,
+        };
+        let (_, _, _, body) = split(packet)?;
+        if body.starts_with('?') || self.gated_to_is.is_dupe_tnc2(packet, now) {
+            return None
+        }
+        q_algorithm(packet, Entry::Rf { igate: self.builder.source(), verified: self.verified }).ok()
+    }
+
+    /// Gates a KISS frame heard on RF, see `rf_to_is()`.
+    pub fn kiss_to_is(&mut self, frame: &[u8], now: SystemTime) -> Result<Option<String>, Error> {
+        let (tnc2, _) = kiss_to_tnc2(frame)?;
+        Ok(self.rf_to_is(&String::from_utf8_lossy(&tnc2), now))
+    }
+
+    /// Gates an AX.25 frame heard on RF, see `rf_to_is()`.
+    pub fn ax25_to_is(&mut self, frame: &[u8], now: SystemTime) -> Result<Option<String>, Error> {
+        let tnc2 = ax25_to_tnc2(frame)?;
+        Ok(self.rf_to_is(&String::from_utf8_lossy(&tnc2), now))
+    }
+
+    /// Gates a packet received from APRS-IS. Returns the third-party packet to send
+    /// on RF, or `None` if the packet stays on APRS-IS.
+    ///
+    /// Messages are gated if the addressee was heard on RF recently and the sender
+    /// was not, and they did not come from an unverified login. The next position of
+    /// the sender is gated as well, so that the addressee knows where it came from.
+    pub fn is_to_rf(&mut self, packet: &str, now: SystemTime) -> Option<String> {
+        self.expire(now);
+        if !self.verified {
+            return None
+        }
+        let packet = packet.trim_end_matches(['\r', '\n']);
+        let (_, _, path, _) = split(packet)?;
+        // packets from unverified logins stay on APRS-IS
+        if path.iter().any(|p| matches!(p.trim_end_matches('*'), "NOGATE" | "RFONLY" | "TCPXX" | "qAX")) {
+            return None
+        }
+        let parsed = Packet::new(packet).ok()?;
+        let source = parsed.source().to_ascii_uppercase();
+        if self.is_local(&source, now) || source.eq_ignore_ascii_case(self.builder.source()) {
+            return None
+        }
+        let is_message = parsed.packet_type() == Some(PacketType::Message);
+        let position = matches!(parsed.packet_type(), Some(PacketType::Location) | Some(PacketType::MicE) | Some(PacketType::Nmea));
+        if is_message {
+            let addressee = parsed.message_destination()?.trim_end().to_ascii_uppercase();
+            if !self.is_local(&addressee, now) {
+                return None
+            }
+        } else if !(position && self.senders.contains_key(&source)) {
+            return None
+        }
+        if self.gated_to_rf.is_dupe_tnc2(packet, now) {
+            return None
+        }
+        let line = self.builder.third_party(packet).ok()?;
+        if is_message {
+            self.messages += 1;
+            self.senders.insert(source, now);
+        } else {
+            self.senders.remove(&source);
+        }
+        Some(line)
+    }
+
+    /// Whether the station was heard on RF within the local window.
+    pub fn is_local(&self, callsign: &str, now: SystemTime) -> bool {
+        match self.heard.get(&callsign.to_ascii_uppercase()) {
+            Some(&heard) => now.duration_since(heard).map_or(true, |age| age <= self.local_window),
+            None => false,
+        }
+    }
+
+    /// Number of stations heard on RF within the local window.
+    pub fn local_stations(&self, now: SystemTime) -> usize {
+        self.heard.keys().filter(|call| self.is_local(call, now)).count()
+    }
+
+    /// Number of messages gated to RF.
+    pub fn messages_gated(&self) -> u32 {
+        self.messages
+    }
+
+    /// Forgets stations not heard on RF within the local window. Gating does this
+    /// too, so that memory use stays bounded.
+    pub fn expire(&mut self, now: SystemTime) {
+        self.last_update = Some(self.last_update.map_or(now, |last| last.max(now)));
+        let fresh = |window: Duration| move |_: &String, t: &mut SystemTime| {
+            now.duration_since(*t).map_or(true, |age| age <= window)
+        };
+        self.heard.retain(fresh(self.local_window));
+        self.senders.retain(fresh(self.local_window));
+    }
+}
+
+/// Answers `?IGATE?` with the number of messages gated and of stations local at the
+/// latest time passed to the `Igate`.
+impl QueryResponder for Igate {
+    fn igate_counts(&self) -> Option<(u32, u32)> {
+        let local = self.last_update.map_or(0, |now| self.local_stations(now));
+        Some((self.messages, local as u32))
+    }
+}
+
+// Source, destination, path and body of a TNC2 packet
+fn split(packet: &str) -> Option<(&str, &str, Vec<&str>, &str)> {
+    let colon = packet.find(':')?;
+    let (header, body) = (&packet[..colon], &packet[colon + 1..]);
+    let gt = header.find('>')?;
+    let mut path: Vec<&str> = header[gt + 1..].split(',').collect();
+    let destination = path.remove(0);
+    if gt == 0 || destination.is_empty() {
+        return None
+    }
+    Some((&header[..gt], destination, path, body))
+}
+
+// Path elements that keep a packet off APRS-IS, or mark it as already from there
+fn no_gate(element: &str) -> bool {
+    matches!(element.trim_end_matches('*'), "NOGATE" | "RFONLY" | "TCPIP" | "TCPXX")
+}
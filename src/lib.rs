@@ -29,37 +29,95 @@
 //! Parsed packet implements `aprs::Packet` trait, see [`aprs` crate documentation](https://docs.rs/aprs) 
 //! for details on how to use the returned value. 
 //! 
+//! `fap::Packet` points into memory allocated by `libfap` and can not leave the thread
+//! it was created on. Use `Packet::to_owned()` to get an `OwnedPacket`, which is a plain
+//! Rust copy of all parsed fields and can be cloned and sent between threads:
+//!
+//! ```rust
+//! extern crate fap;
+//! use std::thread;
+//!
+//! let raw = "DISCOF>APT314,RAZOR*,WIDE1*,qAS,GERLCH:/022526h4046.40N/11912.12W-347/001/";
+//! let owned = fap::Packet::new(raw).unwrap().to_owned();
+//! let handle = thread::spawn(move || owned.src_callsign.clone());
+//! assert_eq!(handle.join().unwrap(), "DISCOF");
+//! ```
+//!
+//! `PacketBuilder` goes the other way and generates packets from Rust values.
+//! `aprsis::Client` receives packets from an APRS-IS server.
+//!
+//! With the `serde` feature enabled `OwnedPacket` implements `Serialize` and `Deserialize`.
+//! Field names follow Ham::APRS::FAP (and aprs.fi), e.g. `srccallsign`, `digipeaters` or `wx`.
+//!
 #![allow(non_upper_case_globals)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(dead_code)]
 
 extern crate aprs;
+extern crate humantime;
+#[cfg(feature = "serde")]
+extern crate serde;
 
+pub mod aprsis;
 mod bind {
     include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 }
+pub mod digipeater;
+mod dupe;
+mod encode;
+mod frame;
+pub mod igate;
+mod owned;
+mod parser;
+mod query;
+pub mod station;
+mod telemetry;
+
 use bind::*;
+pub use owned::{OwnedPacket, PacketType, PositionFormat, Digipeater, Weather, Telemetry};
+pub use parser::{Parser, ParserBuilder, CommentMode};
+pub use dupe::DupeChecker;
+pub use telemetry::{TelemetryMetadata, TelemetryRegistry, ScaledTelemetry, ScaledValue, ScaledBit};
+pub use query::{Query, QueryType, Footprint, QueryResponder};
+pub use frame::{kiss_to_tnc2, tnc2_to_kiss, ax25_to_tnc2, tnc2_to_ax25};
+pub use encode::{PacketBuilder, PositionReport, Phg, Timestamp, MicEReport, MicEMessage, Bulletin, Message,
+    MessageIds, MAX_MESSAGE_LEN, ObjectReport, ItemReport, TelemetryReport, TelemetryDefinition,
+    WeatherReport, StatusReport};
+use aprsis::QConstruct;
 use aprs::{Packet as AprsPacket, Position, Feet, Knots, KilometersPerHour,
-    Meters, Degrees, Fahrenheits, Symbol};
+    MetersPerSecond, Meters, Degrees, Fahrenheits, Celsius, Symbol};
 use std::ffi::{CStr, CString, NulError};
-use std::os::raw::{c_uint, c_short, c_char};
-use std::sync::{Once, ONCE_INIT};
+use std::os::raw::{c_uint, c_short, c_char, c_void};
+use std::ptr;
+use std::slice;
+use std::sync::Once;
 use std::borrow::Cow;
 use std::vec::Vec;
 use std::fmt;
+use std::io;
+use std::mem;
 use std::time::{SystemTime, Duration, UNIX_EPOCH};
 
 
 #[derive(Debug)]
 pub enum Error{
     NulInInputData(NulError),
+    /// Packet was parsed, but its type is not in the parser's allowlist
+    NotAccepted(Option<PacketType>),
+    /// Packet can not be generated from the given values
+    Encode(String),
+    /// Network error of an APRS-IS connection
+    Io(io::Error),
     Other(String)
 }
 impl std::error::Error for Error {
     fn description(&self) -> &str {
         match self {
             Error::NulInInputData(_) => "input data must not contain any nulls",
+            Error::NotAccepted(_) => "packet type is not accepted by the parser",
+            Error::Encode(msg) => msg.as_str(),
+            Error::Io(_) => "APRS-IS connection failed",
             Error::Other(msg) => msg.as_str(), 
         }
     }
@@ -67,6 +125,9 @@ impl std::error::Error for Error {
     fn cause(&self) -> Option<&std::error::Error> {
         match self {
             Error::NulInInputData(ref err) => Some(err),
+            Error::NotAccepted(_) => None,
+            Error::Encode(_) => None,
+            Error::Io(ref err) => Some(err),
             Error::Other(_) => None, 
         }        
     }
@@ -75,16 +136,61 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Error::NulInInputData(err) => write!(f, "input data must not contain any nulls: {}", err),
+            Error::NotAccepted(Some(t)) => write!(f, "packet type {} is not accepted", t),
+            Error::NotAccepted(None) => write!(f, "packet of unknown type is not accepted"),
+            Error::Encode(msg) => write!(f, "can not encode packet: {}", msg),
+            Error::Io(err) => write!(f, "APRS-IS connection failed: {}", err),
             Error::Other(msg) => write!(f, "{}", msg), 
         }        
     }
 }
 
-static INIT: Once = ONCE_INIT;
+extern "C" {
+    fn free(ptr: *mut c_void);
+}
+
+static INIT: Once = Once::new();
+
+// Keeps libfap initialized for the rest of the process, for callers without a `Parser`
+fn init() {
+    INIT.call_once(|| {
+        mem::forget(Parser::new());
+    });
+}
+
+unsafe fn opt_val<T: Copy>(ptr: *const T) -> Option<T> {
+    if ptr.is_null() {
+        return None
+    }
+    Some(*ptr)
+}
+
+unsafe fn opt_str<'a>(ptr: *const c_char) -> Option<Cow<'a, str>> {
+    if ptr.is_null() {
+        return None
+    }
+    Some(CStr::from_ptr(ptr).to_string_lossy())
+}
+
+// For the fields libfap does not NUL-terminate
+unsafe fn opt_bytes<'a>(ptr: *const c_char, len: c_uint) -> Option<Cow<'a, str>> {
+    if ptr.is_null() {
+        return None
+    }
+    Some(String::from_utf8_lossy(slice::from_raw_parts(ptr as *const u8, len as usize)))
+}
+
+fn opt_char(c: c_char) -> Option<char> {
+    if c == 0 {
+        return None
+    }
+    Some(c as u8 as char)
+}
 
 #[derive(Debug)]
 pub struct Packet { 
     ptr: *mut fap_packet_t, 
+    third_party_header: Option<String>,
 }
 
 impl Drop for Packet {
@@ -96,19 +202,31 @@ impl Drop for Packet {
 }
 
 impl Packet {
+    /// Parses a packet in TNC2 format.
+    ///
+    /// This uses a process-wide `Parser` that is never dropped, so `libfap` stays
+    /// initialized until the process exits. Create a `Parser` explicitly if you need
+    /// `fap_cleanup()` to be called.
     pub fn new<T: Into<Vec<u8>>>(data: T) -> Result<Packet, Error> {
+        init();
+        Packet::parse(data, false, None)
+    }
+
+    // Caller must hold a `Parser`, so that libfap is initialized
+    fn parse<T: Into<Vec<u8>>>(data: T, is_ax25: bool, reference_time: Option<SystemTime>) -> Result<Packet, Error> {
         let data = data.into();
         let len = data.len();
         let data = CString::new(data).map_err(|e| Error::NulInInputData(e))?;
+        let reference_time = reference_time
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as time_t)
+            .unwrap_or(0);
         unsafe {
-            INIT.call_once(|| {
-                fap_init();
-            });
-            let ptr = fap_parseaprs(data.as_ptr() as *const c_char, len as c_uint, 0 as c_short);
+            let ptr = fap_parseaprs_at(data.as_ptr() as *const c_char, len as c_uint, is_ax25 as c_short, reference_time);
             if ptr.is_null() {
                 return Err(Error::Other("libfap returned null value - allocation failure?".to_string()))
             }   
-            let packet = Packet{ ptr }; 
+            let packet = Packet{ ptr, third_party_header: None }; 
             if !packet.fap().error_code.is_null() {
                 let buf = &mut [0 as i8; 64];
                 fap_explain_error(*packet.fap().error_code, buf.as_mut_ptr() as *mut c_char);
@@ -124,6 +242,47 @@ impl Packet {
         unsafe { *self.ptr }
     }
 
+    // Rewrites the comment in place the way Ham::APRS::FAP reports it,
+    // dropping it altogether if nothing is left
+    fn perl_comment(&mut self) {
+        let is_mice = self.format() == Some(PositionFormat::MicE);
+        let fap = unsafe { &mut *self.ptr };
+        if fap.comment.is_null() {
+            return
+        }
+        // like Perl, control characters are dropped wherever they are
+        let bytes: Vec<u8> = unsafe { slice::from_raw_parts(fap.comment as *const u8, fap.comment_len as usize) }
+            .iter().cloned().filter(|b| !b.is_ascii_control()).collect();
+        // Perl drops a single '/' or space delimiter left after PHG, course/speed and
+        // other data extensions; mic-e has no data extensions
+        let mut start = 0;
+        if !is_mice && bytes.first().map(|b| *b == b'/' || *b == b' ').unwrap_or(false) {
+            start = 1;
+        }
+        let start = bytes[start..].iter().position(|b| *b != b' ').map(|i| i + start).unwrap_or(bytes.len());
+        let end = bytes.iter().rposition(|b| *b != b' ').map(|i| i + 1).unwrap_or(start);
+        unsafe {
+            if start >= end {
+                free(fap.comment as *mut c_void);
+                fap.comment = ptr::null_mut();
+                fap.comment_len = 0;
+            } else if end - start < fap.comment_len as usize {
+                ptr::copy_nonoverlapping(bytes[start..].as_ptr(), fap.comment as *mut u8, end - start);
+                *fap.comment.add(end - start) = 0;
+                fap.comment_len = (end - start) as c_uint;
+            }
+        }
+    }
+
+    fn forget_orig_packet(&mut self) {
+        let fap = unsafe { &mut *self.ptr };
+        if !fap.orig_packet.is_null() {
+            unsafe { free(fap.orig_packet as *mut c_void); }
+            fap.orig_packet = ptr::null_mut();
+            fap.orig_packet_len = 0;
+        }
+    }
+
     pub fn header(&self) -> Cow<str> {
         debug_assert!(!self.fap().header.is_null());
         unsafe{ CStr::from_ptr(self.fap().header) }.to_string_lossy()
@@ -133,6 +292,257 @@ impl Packet {
         debug_assert!(!self.fap().body.is_null());
         unsafe{ CStr::from_ptr(self.fap().body) }.to_string_lossy()
     }
+
+    /// Copies all parsed fields into an `OwnedPacket`, which can be sent to
+    /// other threads and outlives `libfap` memory.
+    pub fn to_owned(&self) -> OwnedPacket {
+        OwnedPacket::from(self)
+    }
+
+    pub fn packet_type(&self) -> Option<PacketType> {
+        let t = unsafe{ opt_val(self.fap().type_) }?;
+        Some(match t {
+            fap_packet_type_t_fapLOCATION => PacketType::Location,
+            fap_packet_type_t_fapOBJECT => PacketType::Object,
+            fap_packet_type_t_fapITEM => PacketType::Item,
+            fap_packet_type_t_fapMICE => PacketType::MicE,
+            fap_packet_type_t_fapNMEA => PacketType::Nmea,
+            fap_packet_type_t_fapWX => PacketType::Wx,
+            fap_packet_type_t_fapMESSAGE => PacketType::Message,
+            fap_packet_type_t_fapCAPABILITIES => PacketType::Capabilities,
+            fap_packet_type_t_fapSTATUS => PacketType::Status,
+            fap_packet_type_t_fapTELEMETRY => PacketType::Telemetry,
+            fap_packet_type_t_fapTELEMETRY_MESSAGE => PacketType::TelemetryMessage,
+            fap_packet_type_t_fapDX_SPOT => PacketType::DxSpot,
+            _ => PacketType::Experimental,
+        })
+    }
+
+    pub fn format(&self) -> Option<PositionFormat> {
+        let f = unsafe{ opt_val(self.fap().format) }?;
+        match f {
+            fap_pos_format_t_fapPOS_COMPRESSED => Some(PositionFormat::Compressed),
+            fap_pos_format_t_fapPOS_UNCOMPRESSED => Some(PositionFormat::Uncompressed),
+            fap_pos_format_t_fapPOS_MICE => Some(PositionFormat::MicE),
+            fap_pos_format_t_fapPOS_NMEA => Some(PositionFormat::Nmea),
+            _ => None,
+        }
+    }
+
+    /// Exact copy of the original packet, if libfap kept one.
+    pub fn orig_packet(&self) -> Option<Cow<str>> {
+        let fap = self.fap();
+        unsafe{ opt_bytes(fap.orig_packet, fap.orig_packet_len) }
+    }
+
+    /// Raw path elements as they appear in the header, e.g. `WIDE1*`.
+    pub fn path(&self) -> Vec<Cow<str>> {
+        let fap = self.fap();
+        if fap.path.is_null() {
+            return Vec::new()
+        }
+        let elems = unsafe{ slice::from_raw_parts(fap.path, fap.path_len as usize) };
+        elems.iter().filter_map(|p| unsafe{ opt_str(*p) }).collect()
+    }
+
+    /// Path elements split into callsign and "has been digipeated" flag.
+    pub fn digipeaters(&self) -> Vec<Digipeater> {
+        self.path().iter().map(|p| Digipeater::from_tnc2(p)).collect()
+    }
+
+    /// Packet carried in a third-party packet `}SRC>DST,PATH:payload`, as IGates send
+    /// packets from APRS-IS on RF. `None` for other packets.
+    ///
+    /// The carried packet is parsed on its own, so that its source is the station that
+    /// sent it rather than the IGate. It may be a third-party packet in turn. Like
+    /// `Packet::new()`, this uses the default options; `Parser::third_party()` applies
+    /// those of a parser.
+    ///
+    /// ```rust
+    /// extern crate aprs;
+    /// extern crate fap;
+    /// use aprs::Packet;
+    ///
+    /// let raw = "N0CALL-10>APRS,WIDE1-1*:}N1CALL>APRS,TCPIP,N0CALL-10*:!4903.50N/07201.75W-";
+    /// let packet = fap::Packet::new(raw).unwrap();
+    /// let inner = packet.third_party().unwrap().unwrap();
+    /// assert_eq!(inner.source(), "N1CALL");
+    /// assert_eq!(inner.latitude(), Some(49.0583333));
+    /// assert_eq!(inner.third_party_header(), Some("N0CALL-10>APRS,WIDE1-1*"));
+    /// ```
+    pub fn third_party(&self) -> Option<Result<Packet, Error>> {
+        init();
+        self.third_party_with(|inner| Packet::parse(inner, false, None))
+    }
+
+    // Caller must hold a `Parser` for `parse`
+    fn third_party_with<F>(&self, parse: F) -> Option<Result<Packet, Error>>
+        where F: FnOnce(&str) -> Result<Packet, Error>
+    {
+        let body = self.body();
+        let inner = body.strip_prefix('}')?;
+        Some(parse(inner).map(|mut packet| {
+            packet.third_party_header = Some(self.header().into_owned());
+            packet
+        }))
+    }
+
+    /// Header of the third-party packet this packet was carried in, if it came from
+    /// `third_party()`.
+    pub fn third_party_header(&self) -> Option<&str> {
+        self.third_party_header.as_deref()
+    }
+
+    /// APRS-IS q construct in the path, with the station following it: the IGate,
+    /// client or server the packet entered APRS-IS at.
+    pub fn q_construct(&self) -> Option<(QConstruct, Cow<str>)> {
+        let mut path = self.path().into_iter();
+        let construct = path.by_ref().find_map(|p| QConstruct::parse(&p))?;
+        Some((construct, path.next()?))
+    }
+
+    pub fn pos_ambiguity(&self) -> Option<u32> {
+        unsafe{ opt_val(self.fap().pos_ambiguity) }
+    }
+
+    /// Datum character from the `!DAO!` extension.
+    pub fn dao_datum_byte(&self) -> Option<char> {
+        opt_char(self.fap().dao_datum_byte)
+    }
+
+    pub fn symbol_table(&self) -> Option<char> {
+        opt_char(self.fap().symbol_table)
+    }
+
+    pub fn symbol_code(&self) -> Option<char> {
+        opt_char(self.fap().symbol_code)
+    }
+
+    pub fn messaging(&self) -> Option<bool> {
+        unsafe{ opt_val(self.fap().messaging) }.map(|v| v != 0)
+    }
+
+    /// Addressee of an APRS message.
+    pub fn message_destination(&self) -> Option<Cow<str>> {
+        unsafe{ opt_str(self.fap().destination) }
+    }
+
+    pub fn message(&self) -> Option<Cow<str>> {
+        unsafe{ opt_str(self.fap().message) }
+    }
+
+    /// Id of the message acknowledged by this packet.
+    pub fn message_ack(&self) -> Option<Cow<str>> {
+        unsafe{ opt_str(self.fap().message_ack) }
+    }
+
+    /// Id of the message rejected by this packet.
+    pub fn message_nack(&self) -> Option<Cow<str>> {
+        unsafe{ opt_str(self.fap().message_nack) }
+    }
+
+    pub fn message_id(&self) -> Option<Cow<str>> {
+        unsafe{ opt_str(self.fap().message_id) }
+    }
+
+    pub fn object_or_item_name(&self) -> Option<Cow<str>> {
+        unsafe{ opt_str(self.fap().object_or_item_name) }
+    }
+
+    /// Object or item status, `false` if it has been killed.
+    pub fn alive(&self) -> Option<bool> {
+        unsafe{ opt_val(self.fap().alive) }.map(|v| v != 0)
+    }
+
+    pub fn gps_fix_status(&self) -> Option<bool> {
+        unsafe{ opt_val(self.fap().gps_fix_status) }.map(|v| v != 0)
+    }
+
+    /// Radio range of the station in kilometers.
+    pub fn radio_range(&self) -> Option<u32> {
+        unsafe{ opt_val(self.fap().radio_range) }
+    }
+
+    /// Power, height, gain and directivity as they appear in the packet.
+    pub fn phg(&self) -> Option<Cow<str>> {
+        unsafe{ opt_str(self.fap().phg) }
+    }
+
+    /// Timestamp as it appears in the packet.
+    pub fn raw_timestamp(&self) -> Option<Cow<str>> {
+        unsafe{ opt_str(self.fap().raw_timestamp) }
+    }
+
+    pub fn nmea_checksum_ok(&self) -> Option<bool> {
+        unsafe{ opt_val(self.fap().nmea_checksum_ok) }.map(|v| v != 0)
+    }
+
+    pub fn wx_report(&self) -> Option<Weather> {
+        let wx = unsafe{ opt_val(self.fap().wx_report) }?;
+        unsafe {
+            Some(Weather {
+                wind_gust: opt_val(wx.wind_gust),
+                wind_dir: opt_val(wx.wind_dir),
+                wind_speed: opt_val(wx.wind_speed),
+                temp: opt_val(wx.temp),
+                temp_in: opt_val(wx.temp_in),
+                rain_1h: opt_val(wx.rain_1h),
+                rain_24h: opt_val(wx.rain_24h),
+                rain_midnight: opt_val(wx.rain_midnight),
+                humidity: opt_val(wx.humidity),
+                humidity_in: opt_val(wx.humidity_in),
+                pressure: opt_val(wx.pressure),
+                luminosity: opt_val(wx.luminosity),
+                snow_24h: opt_val(wx.snow_24h),
+                soft: opt_str(wx.soft).map(|v| v.into_owned()),
+            })
+        }
+    }
+
+    pub fn telemetry(&self) -> Option<Telemetry> {
+        let tlm = unsafe{ opt_val(self.fap().telemetry) }?;
+        let bits: String = tlm.bits.iter().map(|b| *b as u8 as char).collect();
+        unsafe {
+            Some(Telemetry {
+                seq: opt_val(tlm.seq),
+                values: [opt_val(tlm.val1), opt_val(tlm.val2), opt_val(tlm.val3),
+                    opt_val(tlm.val4), opt_val(tlm.val5)],
+                bits: if bits.chars().all(|c| c == '?') { None } else { Some(bits) },
+            })
+        }
+    }
+
+    /// Telemetry metadata carried by a `PARM.`, `UNIT.`, `EQNS.` or `BITS.` message.
+    /// It applies to the station the message is addressed to, see
+    /// `message_destination()`.
+    pub fn telemetry_definition(&self) -> Option<TelemetryDefinition> {
+        if !matches!(self.packet_type(), Some(PacketType::TelemetryMessage) | Some(PacketType::Message)) {
+            return None
+        }
+        TelemetryDefinition::parse(&self.message()?)
+    }
+
+    /// Mic-E message bits, three characters `0`, `1` or `2`.
+    pub fn messagebits(&self) -> Option<Cow<str>> {
+        unsafe{ opt_str(self.fap().messagebits) }
+    }
+
+    pub fn status(&self) -> Option<Cow<str>> {
+        let fap = self.fap();
+        unsafe{ opt_bytes(fap.status, fap.status_len) }
+    }
+
+    /// Capabilities as key and optional value pairs, in packet order.
+    pub fn capabilities(&self) -> Vec<(Cow<str>, Option<Cow<str>>)> {
+        let fap = self.fap();
+        if fap.capabilities.is_null() {
+            return Vec::new()
+        }
+        let caps = unsafe{ slice::from_raw_parts(fap.capabilities, 2 * fap.capabilities_len as usize) };
+        caps.chunks(2)
+            .filter_map(|kv| unsafe{ opt_str(kv[0]).map(|k| (k, opt_str(kv[1]))) })
+            .collect()
+    }
 }
 
 impl AprsPacket for Packet {
@@ -227,15 +637,21 @@ impl AprsPacket for Packet {
     }
 
     fn temperature(&self) -> Option<Fahrenheits> {
-        unimplemented!();
+        self.wx_report()
+            .and_then(|wx| wx.temp)
+            .map(|v| Fahrenheits::from(Celsius(v as f32)))
     }
 
     fn wind_direction(&self) -> Option<Degrees> {
-        unimplemented!();
+        self.wx_report()
+            .and_then(|wx| wx.wind_dir)
+            .map(|v| Degrees(v as f32))
     }
 
     fn wind_speed(&self) -> Option<Knots> {
-        unimplemented!();
+        self.wx_report()
+            .and_then(|wx| wx.wind_speed)
+            .map(|v| Knots::from(MetersPerSecond(v as f32)))
     }
 }
 
@@ -136,6 +136,22 @@ regex_t fapint_regex_hopcount1, fapint_regex_hopcount2;
 /* Regex status flag. */
 short fapint_initialized = 0;
 
+/* Reference time for timestamp resolution, zero means current time. */
+__thread time_t fapint_reference_time = 0;
+
+
+
+fap_packet_t* fap_parseaprs_at(char const* input, unsigned int const input_len, short const is_ax25, time_t const reference_time)
+{
+	fap_packet_t* result;
+
+	fapint_reference_time = reference_time;
+	result = fap_parseaprs(input, input_len, is_ax25);
+	fapint_reference_time = 0;
+
+	return result;
+}
+
 
 
 fap_packet_t* fap_parseaprs(char const* input, unsigned int const input_len, short const is_ax25)
@@ -817,7 +833,7 @@ double fap_direction(double lon0, double lat0, double lon1, double lat1)
 
 int fap_count_digihops(fap_packet_t const* packet)
 {
-	int i, len;
+	int i, len, hops;
 	unsigned int hopcount = 0, n, N;
 	short wasdigied;
 	char* element;
@@ -872,10 +888,10 @@ int fap_count_digihops(fap_packet_t const* packet)
 			n = call_ssid[matches[1].rm_so] - 48;
 			N = call_ssid[matches[2].rm_so] - 48;
 			
-			/* Add difference to hopcount, if not negative. */
-			if ( (i = n - N) >= 0 )
+			/* Add difference to hopcount, if not negative. Keep i, it is the loop counter. */
+			if ( (hops = (int)n - (int)N) >= 0 )
 			{
-				hopcount += i;
+				hopcount += hops;
 			}
 		}
 		else
@@ -1163,6 +1179,7 @@ int fap_ax25_to_tnc2(char const* ax25frame, unsigned int ax25frame_len,
 					/* We have a source callsign, copy it to the final frame directly. */
 					output_len = sprintf(output, "%s>%s", checked_call, dst_callsign);
 					free(dst_callsign);
+					dst_callsign = NULL;
 					free(checked_call);
 				}
 				else if ( header_len > 14 )
@@ -1241,6 +1258,9 @@ int fap_ax25_to_tnc2(char const* ax25frame, unsigned int ax25frame_len,
 		}
 	}
 	
+	/* Destination callsign is left over if we bailed out before the source. */
+	if ( dst_callsign ) free(dst_callsign);
+	
 	/* Copy result to output. */
 	if ( output_len > *tnc2frame_len ) output_len = *tnc2frame_len;
 	memcpy(tnc2frame, output, output_len);
@@ -1260,13 +1280,16 @@ int fap_tnc2_to_kiss(char const* tnc2frame, unsigned int tnc2frame_len, unsigned
 
 	/* Initialize slot for starting FEND and tnc id by skipping two first bytes of our conversion buffer. */
 	ax25frame_len = 2*FRAME_MAXLEN-2;
-	memset(ax25frame, 0, 2);
+	memset(ax25frame, 0, sizeof(ax25frame));
 	
 	/* Convert into AX.25-frame. */
 	if ( !fap_tnc2_to_ax25(tnc2frame, tnc2frame_len, ax25frame+2, &ax25frame_len) )
 	{
-		strcpy(kissframe, ax25frame);
-		*kissframe_len = strlen(kissframe);
+		/* Pass on the error message, if any. */
+		ax25frame_len = strlen(ax25frame+2)+1;
+		if ( ax25frame_len > *kissframe_len ) ax25frame_len = *kissframe_len;
+		memcpy(kissframe, ax25frame+2, ax25frame_len);
+		*kissframe_len = ax25frame_len;
 		return 0;
 	}
 	ax25frame_len += 2;
@@ -1331,7 +1354,7 @@ int fap_tnc2_to_ax25(char const* tnc2frame, unsigned int tnc2frame_len,
 	int digicall_ssid_num = 0;
 
 	int retval = 1, len, i;
-	char* tmp_str;
+	char* tmp_str, *strtok_save;
 	
 	unsigned int const matchcount = 6;
 	regmatch_t matches[matchcount];
@@ -1472,7 +1495,7 @@ int fap_tnc2_to_ax25(char const* tnc2frame, unsigned int tnc2frame_len,
 		if ( digipeaters )
 		{
 			/* Split into parts. */
-			tmp_str = strtok(digipeaters+1, ",");
+			tmp_str = strtok_r(digipeaters+1, ",", &strtok_save);
 			digi_count = 0;
 			while ( tmp_str != NULL )
 			{
@@ -1508,7 +1531,7 @@ int fap_tnc2_to_ax25(char const* tnc2frame, unsigned int tnc2frame_len,
 					}
 					
 					/* Check for next part. */
-					tmp_str = strtok(NULL, ",");
+					tmp_str = strtok_r(NULL, ",", &strtok_save);
 					
 					/* Add plain callsign frame. */
 					for ( i = 0; i < 6; ++i )
@@ -375,6 +375,17 @@ typedef struct
 fap_packet_t* fap_parseaprs(char const* input, unsigned int const input_len, short const is_ax25);
 
 
+/// The parser, with timestamps resolved relative to given time.
+/**
+ * Works like fap_parseaprs(), but day-hour-minute and hour-minute-second
+ * timestamps are resolved relative to reference_time instead of the
+ * current time. Useful when parsing old packets from a log.
+ *
+ * \param reference_time Unix time to resolve timestamps against. If 0, current time is used.
+*/
+fap_packet_t* fap_parseaprs_at(char const* input, unsigned int const input_len, short const is_ax25, time_t const reference_time);
+
+
 /// Return human-readable error message for given error code.
 /**
  * \param error Error code from fap_packet_t.
@@ -620,8 +620,10 @@ int fapint_parse_mice(fap_packet_t* packet, char const* input, unsigned int cons
 	/* If there's something left, create working copy of it. */
 	if ( (len = input_len - 8) > 0 )
 	{
-		rest = malloc(len);
+		rest = malloc(len+1);
+		if ( !rest ) return 0;
 		memcpy(rest, input+8, len);
+		rest[len] = 0;
 	}
 	else
 	{
@@ -760,7 +762,7 @@ time_t fapint_parse_timestamp(char const* input)
 	char type;
 	struct tm now_struct, fwd_struct, back_struct, tmp_struct;
 	time_t thismonth, nextmonth, prevmonth, result;
-	const time_t now = time(NULL);
+	const time_t now = fapint_reference_time ? fapint_reference_time : time(NULL);
 	
 	unsigned int const matchcount = 5;
 	regmatch_t matches[matchcount];
@@ -902,7 +904,7 @@ int fapint_parse_compressed(fap_packet_t* packet, char const* input)
 	char symboltable, symbolcode;
 	char lat[4], lon[4];
 	char c1, s1, comptype;
-	char cs;
+	int cs;
 	
 	/* Validate compressed position and things. */
 	if ( strlen(input) < 13 )
@@ -974,7 +976,7 @@ int fapint_parse_compressed(fap_packet_t* packet, char const* input)
 	/* Save symbol table. Table chars (a-j) are converted to numbers 0-9. */
 	if ( symboltable >= 'a' && symboltable <= 'j' )
 	{
-		symboltable -= 81;
+		symboltable -= 49;
 	}
 	packet->symbol_table = symboltable;
 	
@@ -1423,7 +1425,7 @@ int fapint_parse_nmea(fap_packet_t* packet, char const* input, unsigned int cons
 	fapint_llist_item_t* nmea_field_list = NULL, *current_elem = NULL;
 	char** nmea_fields = NULL;
 	unsigned int nmea_field_count;
-	char* tmp_str;
+	char* tmp_str, *strtok_save;
 	
 	char buf_3b[3];
 	unsigned int year, month, day, hours, mins, secs;
@@ -1520,7 +1522,7 @@ int fapint_parse_nmea(fap_packet_t* packet, char const* input, unsigned int cons
 	}
 	
 	/* Split to NMEA fields. */
-	tmp_str = strtok(rest, ",");
+	tmp_str = strtok_r(rest, ",", &strtok_save);
 	nmea_field_count = 0;
 	while ( tmp_str != NULL )
 	{
@@ -1554,7 +1556,7 @@ int fapint_parse_nmea(fap_packet_t* packet, char const* input, unsigned int cons
 		nmea_field_count++;
 
 		/* Try to get next. */
-		tmp_str = strtok(NULL, ",");
+		tmp_str = strtok_r(NULL, ",", &strtok_save);
 	}
 	if ( !retval )
 	{
@@ -2480,7 +2482,7 @@ int fapint_parse_capabilities(fap_packet_t* packet, char const* input, unsigned
 			/* Check that splitpos is not first or last char. */
 			if ( sepa_pos < input_len )
 			{
-				packet->capabilities[i] = fapint_remove_part(current_elem->text, strlen(current_elem->text), sepa_pos, strlen(current_elem->text), &foo);
+				packet->capabilities[i] = fapint_remove_part(current_elem->text, strlen(current_elem->text), sepa_pos+1, strlen(current_elem->text), &foo);
 				packet->capabilities[i+1] = fapint_remove_part(current_elem->text, strlen(current_elem->text), 0, sepa_pos+2, &foo);
 				saved = 1;
 			}
@@ -2902,7 +2904,7 @@ int fapint_parse_wx(fap_packet_t* packet, char const* input, unsigned int const
 			return 0;
 		}
 		*packet->wx_report->luminosity = atoi(buf_5b);
-		if ( input[matches[1].rm_so] == 'l' )
+		if ( rest[matches[1].rm_so] == 'l' )
 		{
 			*packet->wx_report->luminosity += 1000;
 		}
@@ -648,6 +648,7 @@ void fapint_init_telemetry_report(fap_telemetry_t* tlm_report)
         tlm_report->val3 = NULL;
         tlm_report->val4 = NULL;
         tlm_report->val5 = NULL;
+        memset(tlm_report->bits, '?', sizeof(tlm_report->bits));
 }
 
 
@@ -49,5 +49,8 @@ extern regex_t fapint_regex_nmea_specou, fapint_regex_nmea_fix, fapint_regex_nme
 extern regex_t fapint_regex_telemetry, fapint_regex_peet_splitter, fapint_regex_kiss_callsign;
 extern regex_t fapint_regex_base91_telemetry;
 
+#include <time.h>
+extern __thread time_t fapint_reference_time;
+
 
 #endif // REGS_H
@@ -0,0 +1,434 @@
+//! Plain Rust representation of a parsed packet.
+//!
+//! `fap::Packet` is a thin view over memory owned by `libfap`, which makes it
+//! cheap to create but impossible to share between threads. `OwnedPacket`
+//! copies everything out of the C structure, so it can be cloned, compared,
+//! sent through channels and kept around after the C allocation is freed.
+
+use aprs::{Packet as AprsPacket, Position, Feet, Knots, KilometersPerHour,
+    MetersPerSecond, Meters, Degrees, Fahrenheits, Celsius, Symbol};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{SystemTime, Duration, UNIX_EPOCH};
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Packet type as detected by `libfap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
+pub enum PacketType {
+    Location,
+    Object,
+    Item,
+    MicE,
+    Nmea,
+    Wx,
+    Message,
+    Capabilities,
+    Status,
+    Telemetry,
+    #[cfg_attr(feature = "serde", serde(rename = "telemetry-message"))]
+    TelemetryMessage,
+    #[cfg_attr(feature = "serde", serde(rename = "dx"))]
+    DxSpot,
+    Experimental,
+}
+
+impl PacketType {
+    /// Type name as used by Ham::APRS::FAP.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PacketType::Location => "location",
+            PacketType::Object => "object",
+            PacketType::Item => "item",
+            PacketType::MicE => "mice",
+            PacketType::Nmea => "nmea",
+            PacketType::Wx => "wx",
+            PacketType::Message => "message",
+            PacketType::Capabilities => "capabilities",
+            PacketType::Status => "status",
+            PacketType::Telemetry => "telemetry",
+            PacketType::TelemetryMessage => "telemetry-message",
+            PacketType::DxSpot => "dx",
+            PacketType::Experimental => "experimental",
+        }
+    }
+}
+
+impl fmt::Display for PacketType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Format of the position report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
+pub enum PositionFormat {
+    Compressed,
+    Uncompressed,
+    MicE,
+    Nmea,
+}
+
+impl PositionFormat {
+    /// Format name as used by Ham::APRS::FAP.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PositionFormat::Compressed => "compressed",
+            PositionFormat::Uncompressed => "uncompressed",
+            PositionFormat::MicE => "mice",
+            PositionFormat::Nmea => "nmea",
+        }
+    }
+}
+
+impl fmt::Display for PositionFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Single element of the digipeater path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Digipeater {
+    /// Callsign or alias, without the trailing `*`.
+    pub call: String,
+    /// True if the element was marked as used (`*` in TNC2 format).
+    #[cfg_attr(feature = "serde", serde(rename = "wasdigied"))]
+    pub digipeated: bool,
+}
+
+impl Digipeater {
+    /// Splits raw TNC2 path element like `WIDE1*` into call and H-bit.
+    pub fn from_tnc2(raw: &str) -> Digipeater {
+        match raw.strip_suffix('*') {
+            Some(call) => Digipeater{ call: call.to_string(), digipeated: true },
+            None => Digipeater{ call: raw.to_string(), digipeated: false },
+        }
+    }
+}
+
+impl fmt::Display for Digipeater {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.call, if self.digipeated { "*" } else { "" })
+    }
+}
+
+/// Weather report, all values in SI units as reported by `libfap`.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct Weather {
+    /// Wind gust in m/s.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub wind_gust: Option<f64>,
+    /// Wind direction in degrees.
+    #[cfg_attr(feature = "serde", serde(rename = "wind_direction", skip_serializing_if = "Option::is_none"))]
+    pub wind_dir: Option<u32>,
+    /// Wind speed in m/s.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub wind_speed: Option<f64>,
+    /// Temperature in degrees Celsius.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub temp: Option<f64>,
+    /// Indoor temperature in degrees Celsius.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub temp_in: Option<f64>,
+    /// Rain from last 1 hour, in millimeters.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub rain_1h: Option<f64>,
+    /// Rain from last day, in millimeters.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub rain_24h: Option<f64>,
+    /// Rain since midnight, in millimeters.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub rain_midnight: Option<f64>,
+    /// Relative humidity percentage.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub humidity: Option<u32>,
+    /// Relative inside humidity percentage.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub humidity_in: Option<u32>,
+    /// Air pressure in millibars.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub pressure: Option<f64>,
+    /// Luminosity in watts per square meter.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub luminosity: Option<u32>,
+    /// Snow fall during last day, in millimeters.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub snow_24h: Option<f64>,
+    /// Software type indicator.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub soft: Option<String>,
+}
+
+/// Telemetry report, either `T#` packet or base91 comment telemetry.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct Telemetry {
+    /// Sequence number.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seq: Option<u32>,
+    /// Analog values, `None` where the value was not present.
+    #[cfg_attr(feature = "serde", serde(rename = "vals"))]
+    pub values: [Option<f64>; 5],
+    /// Digital bits as ASCII `0` and `1`, `None` if the report sent no bits.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub bits: Option<String>,
+}
+
+/// Owned copy of everything `libfap` extracted from a packet.
+///
+/// Field names and units follow `fap_packet_t`; see `fap.h` for details.
+/// When serialized, keys follow Ham::APRS::FAP instead.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct OwnedPacket {
+    #[cfg_attr(feature = "serde", serde(rename = "type", skip_serializing_if = "Option::is_none"))]
+    pub packet_type: Option<PacketType>,
+    #[cfg_attr(feature = "serde", serde(rename = "origpacket", skip_serializing_if = "Option::is_none"))]
+    pub orig_packet: Option<String>,
+    pub header: String,
+    pub body: String,
+    #[cfg_attr(feature = "serde", serde(rename = "srccallsign"))]
+    pub src_callsign: String,
+    #[cfg_attr(feature = "serde", serde(rename = "dstcallsign", skip_serializing_if = "Option::is_none"))]
+    pub dst_callsign: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub digipeaters: Vec<Digipeater>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub latitude: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub longitude: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub format: Option<PositionFormat>,
+    /// Position resolution in meters.
+    #[cfg_attr(feature = "serde", serde(rename = "posresolution", skip_serializing_if = "Option::is_none"))]
+    pub pos_resolution: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(rename = "posambiguity", skip_serializing_if = "Option::is_none"))]
+    pub pos_ambiguity: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(rename = "daodatumbyte", skip_serializing_if = "Option::is_none"))]
+    pub dao_datum_byte: Option<char>,
+    /// Altitude in meters.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub altitude: Option<f64>,
+    /// Course in degrees, 0 is unknown and 360 is north.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub course: Option<u32>,
+    /// Speed in km/h.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub speed: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(rename = "symboltable", skip_serializing_if = "Option::is_none"))]
+    pub symbol_table: Option<char>,
+    #[cfg_attr(feature = "serde", serde(rename = "symbolcode", skip_serializing_if = "Option::is_none"))]
+    pub symbol_code: Option<char>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub messaging: Option<bool>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub destination: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub message: Option<String>,
+    #[cfg_attr(feature = "serde", serde(rename = "messageack", skip_serializing_if = "Option::is_none"))]
+    pub message_ack: Option<String>,
+    #[cfg_attr(feature = "serde", serde(rename = "messagerej", skip_serializing_if = "Option::is_none"))]
+    pub message_nack: Option<String>,
+    #[cfg_attr(feature = "serde", serde(rename = "messageid", skip_serializing_if = "Option::is_none"))]
+    pub message_id: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub comment: Option<String>,
+    #[cfg_attr(feature = "serde", serde(rename = "objectname", skip_serializing_if = "Option::is_none"))]
+    pub object_name: Option<String>,
+    #[cfg_attr(feature = "serde", serde(rename = "itemname", skip_serializing_if = "Option::is_none"))]
+    pub item_name: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub alive: Option<bool>,
+    #[cfg_attr(feature = "serde", serde(rename = "gpsfixstatus", skip_serializing_if = "Option::is_none"))]
+    pub gps_fix_status: Option<bool>,
+    /// Radio range in kilometers.
+    #[cfg_attr(feature = "serde", serde(rename = "radiorange", skip_serializing_if = "Option::is_none"))]
+    pub radio_range: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub phg: Option<String>,
+    /// Seconds since UNIX epoch.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub timestamp: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub raw_timestamp: Option<String>,
+    #[cfg_attr(feature = "serde", serde(rename = "checksumok", skip_serializing_if = "Option::is_none"))]
+    pub nmea_checksum_ok: Option<bool>,
+    #[cfg_attr(feature = "serde", serde(rename = "wx", skip_serializing_if = "Option::is_none"))]
+    pub wx_report: Option<Weather>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub telemetry: Option<Telemetry>,
+    #[cfg_attr(feature = "serde", serde(rename = "mbits", skip_serializing_if = "Option::is_none"))]
+    pub messagebits: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub status: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "BTreeMap::is_empty"))]
+    pub capabilities: BTreeMap<String, Option<String>>,
+    /// Header of the third-party packet this packet was carried in.
+    #[cfg_attr(feature = "serde", serde(rename = "thirdpartyheader", skip_serializing_if = "Option::is_none"))]
+    pub third_party_header: Option<String>,
+}
+
+impl<'a> From<&'a ::Packet> for OwnedPacket {
+    fn from(p: &'a ::Packet) -> OwnedPacket {
+        let owned = |v: Option<Cow<str>>| v.map(|v| v.into_owned());
+        let f = p.fap();
+        let name = owned(p.object_or_item_name());
+        let is_item = p.packet_type() == Some(PacketType::Item);
+        OwnedPacket {
+            packet_type: p.packet_type(),
+            orig_packet: owned(p.orig_packet()),
+            header: p.header().into_owned(),
+            body: p.body().into_owned(),
+            src_callsign: p.source().into_owned(),
+            dst_callsign: owned(p.destination()),
+            digipeaters: p.digipeaters(),
+            latitude: unsafe { ::opt_val(f.latitude) },
+            longitude: unsafe { ::opt_val(f.longitude) },
+            format: p.format(),
+            pos_resolution: unsafe { ::opt_val(f.pos_resolution) },
+            pos_ambiguity: p.pos_ambiguity(),
+            dao_datum_byte: p.dao_datum_byte(),
+            altitude: unsafe { ::opt_val(f.altitude) },
+            course: unsafe { ::opt_val(f.course) },
+            speed: unsafe { ::opt_val(f.speed) },
+            symbol_table: p.symbol_table(),
+            symbol_code: p.symbol_code(),
+            messaging: p.messaging(),
+            destination: owned(p.message_destination()),
+            message: owned(p.message()),
+            message_ack: owned(p.message_ack()),
+            message_nack: owned(p.message_nack()),
+            message_id: owned(p.message_id()),
+            comment: owned(p.comment()),
+            object_name: if is_item { None } else { name.clone() },
+            item_name: if is_item { name } else { None },
+            alive: p.alive(),
+            gps_fix_status: p.gps_fix_status(),
+            radio_range: p.radio_range(),
+            phg: owned(p.phg()),
+            timestamp: unsafe { ::opt_val(f.timestamp) }.map(|v| v as u64),
+            raw_timestamp: owned(p.raw_timestamp()),
+            nmea_checksum_ok: p.nmea_checksum_ok(),
+            wx_report: p.wx_report(),
+            telemetry: p.telemetry(),
+            messagebits: owned(p.messagebits()),
+            status: owned(p.status()),
+            capabilities: p.capabilities().into_iter()
+                .map(|(k, v)| (k.into_owned(), v.map(|v| v.into_owned())))
+                .collect(),
+            third_party_header: p.third_party_header().map(|h| h.to_string()),
+        }
+    }
+}
+
+impl From<::Packet> for OwnedPacket {
+    fn from(p: ::Packet) -> OwnedPacket {
+        OwnedPacket::from(&p)
+    }
+}
+
+impl OwnedPacket {
+    /// Parses the packet and immediately detaches it from `libfap` memory.
+    pub fn new<T: Into<Vec<u8>>>(data: T) -> Result<OwnedPacket, ::Error> {
+        ::Packet::new(data).map(OwnedPacket::from)
+    }
+
+    /// Name of the object or item, whichever is present.
+    pub fn object_or_item_name(&self) -> Option<&str> {
+        self.object_name.as_ref().or(self.item_name.as_ref()).map(|v| v.as_str())
+    }
+
+    /// Digipeater path in TNC2 format, e.g. `WIDE1*,WIDE2-1,qAR,CALL`.
+    pub fn path(&self) -> String {
+        self.digipeaters.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(",")
+    }
+}
+
+impl AprsPacket for OwnedPacket {
+    fn source(&self) -> Cow<str> {
+        Cow::Borrowed(&self.src_callsign)
+    }
+
+    fn symbol(&self) -> Symbol {
+        Symbol::from_table(self.symbol_table.unwrap_or('\0') as u8, self.symbol_code.unwrap_or('\0') as u8)
+    }
+
+    fn timestamp(&self) -> Option<SystemTime> {
+        self.timestamp.map(|ts| UNIX_EPOCH + Duration::from_secs(ts))
+    }
+
+    fn destination(&self) -> Option<Cow<str>> {
+        self.dst_callsign.as_ref().map(|v| Cow::Borrowed(v.as_str()))
+    }
+
+    fn comment(&self) -> Option<Cow<str>> {
+        self.comment.as_ref().map(|v| Cow::Borrowed(v.as_str()))
+    }
+
+    fn latitude(&self) -> Option<f32> {
+        self.latitude.map(|v| v as f32)
+    }
+
+    fn longitude(&self) -> Option<f32> {
+        self.longitude.map(|v| v as f32)
+    }
+
+    fn precision(&self) -> Option<Feet> {
+        self.pos_resolution.map(|v| Feet::from(Meters(v as f32)))
+    }
+
+    fn position(&self) -> Option<Position> {
+        let lat = self.latitude()?;
+        let lng = self.longitude()?;
+        match self.precision() {
+            Some(p) => Some(Position::from_latlng_precise(lat, lng, p)),
+            None => Some(Position::from_latlng(lat, lng))
+        }
+    }
+
+    fn speed(&self) -> Option<Knots> {
+        self.speed.map(|v| Knots::from(KilometersPerHour(v as f32)))
+    }
+
+    fn course(&self) -> Option<Degrees> {
+        match self.course {
+            None | Some(0) => None, // 0 means "unknown" in libfap
+            Some(v) => Some(Degrees((v % 360) as f32)),
+        }
+    }
+
+    fn altitude(&self) -> Option<Feet> {
+        self.altitude.map(|v| Feet::from(Meters(v as f32)))
+    }
+
+    fn temperature(&self) -> Option<Fahrenheits> {
+        self.wx_report.as_ref()
+            .and_then(|wx| wx.temp)
+            .map(|v| Fahrenheits::from(Celsius(v as f32)))
+    }
+
+    fn wind_direction(&self) -> Option<Degrees> {
+        self.wx_report.as_ref()
+            .and_then(|wx| wx.wind_dir)
+            .map(|v| Degrees(v as f32))
+    }
+
+    fn wind_speed(&self) -> Option<Knots> {
+        self.wx_report.as_ref()
+            .and_then(|wx| wx.wind_speed)
+            .map(|v| Knots::from(MetersPerSecond(v as f32)))
+    }
+}
+
+impl fmt::Display for OwnedPacket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:9} {:19} @ {:?}", self.source(), format!("{:?}", self.symbol()), self.position())
+    }
+}
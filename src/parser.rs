@@ -0,0 +1,207 @@
+//! Explicit control over `libfap` global state.
+
+use bind::{fap_init, fap_cleanup};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use {Packet, PacketType, Error};
+
+// Number of live `Parser` handles
+static HANDLES: Mutex<usize> = Mutex::new(0);
+
+/// Handle to the initialized `libfap` library.
+///
+/// `libfap` compiles its regular expressions into global variables in `fap_init()` and
+/// releases them in `fap_cleanup()`. Every `Parser` holds a reference to that global state:
+/// creating the first handle initializes the library and dropping the last one cleans it
+/// up, so tools like valgrind see no leaks. Clones share the same state.
+///
+/// Parsing is thread safe. The compiled expressions are only ever used through POSIX
+/// `regexec()`, which may be called concurrently on the same expression, and the only
+/// other global, the reference time, is thread-local. The library can not be cleaned up while any `Parser`
+/// exists, so `Parser` is `Send` and `Sync` and can be shared between threads freely.
+/// Parsed packets do not depend on the parser and may outlive it.
+///
+/// ```rust
+/// extern crate fap;
+///
+/// let parser = fap::Parser::new();
+/// let packet = parser.parse("N0CALL>APRS:>Hello").unwrap();
+/// drop(parser); // fap_cleanup() is called here
+/// assert_eq!(packet.status().unwrap(), "Hello");
+/// ```
+///
+/// Use `Parser::builder()` to change parsing options:
+///
+/// ```rust
+/// extern crate aprs;
+/// extern crate fap;
+/// use aprs::Packet;
+/// use fap::{PacketType, CommentMode};
+///
+/// let parser = fap::Parser::builder()
+///     .accept(&[PacketType::Location, PacketType::Object])
+///     .comment_mode(CommentMode::PerlCompatible)
+///     .build();
+/// let packet = parser.parse("N0CALL>APRS:!4903.50N/07201.75W-PHG2360/ Test  ").unwrap();
+/// assert_eq!(packet.comment().unwrap(), "Test");
+/// assert!(parser.parse("N0CALL>APRS:>Status").is_err());
+/// ```
+#[derive(Debug)]
+pub struct Parser {
+    options: ParserBuilder,
+}
+
+impl Parser {
+    pub fn new() -> Parser {
+        ParserBuilder::new().build()
+    }
+
+    pub fn builder() -> ParserBuilder {
+        ParserBuilder::new()
+    }
+
+    /// Parses a packet in TNC2 format.
+    pub fn parse<T: Into<Vec<u8>>>(&self, data: T) -> Result<Packet, Error> {
+        self.parse_as(data, self.options.ax25)
+    }
+
+    /// Packet carried in a third-party packet, parsed with this parser's options. See
+    /// `Packet::third_party()`. The carried packet is in TNC2 format whatever the
+    /// `ax25` option says.
+    ///
+    /// ```rust
+    /// extern crate aprs;
+    /// extern crate fap;
+    /// use aprs::Packet;
+    /// use fap::{CommentMode, Parser};
+    ///
+    /// let parser = Parser::builder().comment_mode(CommentMode::PerlCompatible).build();
+    /// let packet = parser.parse("N0CALL-10>APRS:}N1CALL>APRS,TCPIP,N0CALL-10*:!4903.50N/07201.75W- Hi ").unwrap();
+    /// let inner = parser.third_party(&packet).unwrap().unwrap();
+    /// assert_eq!(inner.comment().unwrap(), "Hi");
+    /// ```
+    pub fn third_party(&self, packet: &Packet) -> Option<Result<Packet, Error>> {
+        packet.third_party_with(|inner| self.parse_as(inner, false))
+    }
+
+    fn parse_as<T: Into<Vec<u8>>>(&self, data: T, ax25: bool) -> Result<Packet, Error> {
+        let opts = &self.options;
+        let mut packet = Packet::parse(data, ax25, opts.reference_time)?;
+        if let Some(ref accept) = opts.accept {
+            let packet_type = packet.packet_type();
+            if !packet_type.map(|t| accept.contains(&t)).unwrap_or(false) {
+                return Err(Error::NotAccepted(packet_type))
+            }
+        }
+        if !opts.keep_original {
+            packet.forget_orig_packet();
+        }
+        if opts.comment_mode == CommentMode::PerlCompatible {
+            packet.perl_comment();
+        }
+        Ok(packet)
+    }
+}
+
+impl Default for Parser {
+    fn default() -> Parser {
+        Parser::new()
+    }
+}
+
+impl Clone for Parser {
+    fn clone(&self) -> Parser {
+        self.options.clone().build()
+    }
+}
+
+impl Drop for Parser {
+    fn drop(&mut self) {
+        let mut handles = HANDLES.lock().unwrap_or_else(|e| e.into_inner());
+        *handles -= 1;
+        if *handles == 0 {
+            unsafe { fap_cleanup(); }
+        }
+    }
+}
+
+/// How `Packet::comment()` is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentMode {
+    /// Exactly as seen in the packet, which is what `libfap` does.
+    Raw,
+    /// Same as Ham::APRS::FAP: control characters are removed, then the `/` or space
+    /// delimiter following data extensions and leading and trailing whitespace.
+    /// Comments that end up empty are not reported at all.
+    PerlCompatible,
+}
+
+/// Options for a `Parser`.
+#[derive(Debug, Clone)]
+pub struct ParserBuilder {
+    ax25: bool,
+    accept: Option<Vec<PacketType>>,
+    keep_original: bool,
+    comment_mode: CommentMode,
+    reference_time: Option<SystemTime>,
+}
+
+impl ParserBuilder {
+    pub fn new() -> ParserBuilder {
+        ParserBuilder {
+            ax25: false,
+            accept: None,
+            keep_original: true,
+            comment_mode: CommentMode::Raw,
+            reference_time: None,
+        }
+    }
+
+    /// Check source callsign and path strictly against AX.25 rules, as needed
+    /// for packets that are going to be transmitted on RF. Off by default,
+    /// which is the right choice for APRS-IS traffic.
+    pub fn ax25(mut self, strict: bool) -> ParserBuilder {
+        self.ax25 = strict;
+        self
+    }
+
+    /// Only accept packets of the given types, others fail with `Error::NotAccepted`.
+    pub fn accept(mut self, types: &[PacketType]) -> ParserBuilder {
+        self.accept = Some(types.to_vec());
+        self
+    }
+
+    /// Keep a copy of the original packet, see `Packet::orig_packet()`. On by default.
+    pub fn keep_original(mut self, keep: bool) -> ParserBuilder {
+        self.keep_original = keep;
+        self
+    }
+
+    /// How to report comments, `CommentMode::Raw` by default.
+    pub fn comment_mode(mut self, mode: CommentMode) -> ParserBuilder {
+        self.comment_mode = mode;
+        self
+    }
+
+    /// Resolve timestamps that lack a date (DHM and HMS formats) relative to the
+    /// given time instead of the current time. Useful when replaying old logs.
+    pub fn reference_time(mut self, time: SystemTime) -> ParserBuilder {
+        self.reference_time = Some(time);
+        self
+    }
+
+    pub fn build(self) -> Parser {
+        let mut handles = HANDLES.lock().unwrap_or_else(|e| e.into_inner());
+        if *handles == 0 {
+            unsafe { fap_init(); }
+        }
+        *handles += 1;
+        Parser{ options: self }
+    }
+}
+
+impl Default for ParserBuilder {
+    fn default() -> ParserBuilder {
+        ParserBuilder::new()
+    }
+}
@@ -0,0 +1,249 @@
+//! Station queries and the replies to them.
+
+use bind::fap_distance;
+use {Error, Message, ObjectReport, PacketBuilder, PositionReport, StatusReport, WeatherReport};
+
+const MILE_TO_KM: f64 = 1.609344;
+
+/// What a query asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryType {
+    /// `?APRS?`, all stations report their position
+    Aprs,
+    /// `?IGATE?`, IGates report their capabilities
+    Igate,
+    /// `?WX?`, weather stations report their weather
+    Weather,
+    /// `?APRSP`, position of the addressee
+    Position,
+    /// `?APRSS`, status of the addressee
+    Status,
+    /// `?APRST` or `?PING?`, the route the query took
+    Trace,
+    /// `?APRSM`, messages the addressee has not had acknowledged yet
+    Messages,
+    /// `?APRSO`, objects the addressee reports
+    Objects,
+}
+
+impl QueryType {
+    fn from_str(query: &str) -> Option<QueryType> {
+        match query {
+            "?APRS?" => Some(QueryType::Aprs),
+            "?IGATE?" => Some(QueryType::Igate),
+            "?WX?" => Some(QueryType::Weather),
+            "?APRSP" => Some(QueryType::Position),
+            "?APRSS" => Some(QueryType::Status),
+            "?APRST" | "?PING?" => Some(QueryType::Trace),
+            "?APRSM" => Some(QueryType::Messages),
+            "?APRSO" => Some(QueryType::Objects),
+            _ => None,
+        }
+    }
+
+    // The queries sent to all stations rather than in a message
+    fn is_general(&self) -> bool {
+        matches!(*self, QueryType::Aprs | QueryType::Igate | QueryType::Weather)
+    }
+}
+
+/// Area a general query is limited to, like `?APRS? 34.02,-117.15,0200`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Footprint {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Radius in km, sent in whole miles
+    pub radius: f64,
+}
+
+impl Footprint {
+    // `lat,lon,radius` with the radius in up to four digits of miles
+    fn parse(footprint: &str) -> Option<Footprint> {
+        let mut fields = footprint.split(',');
+        let latitude: f64 = fields.next()?.trim().parse().ok()?;
+        let longitude: f64 = fields.next()?.trim().parse().ok()?;
+        let radius = fields.next()?.trim();
+        if fields.next().is_some() || radius.is_empty() || radius.len() > 4 || !radius.bytes().all(|b| b.is_ascii_digit()) {
+            return None
+        }
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return None
+        }
+        Some(Footprint {
+            latitude,
+            longitude,
+            radius: radius.parse::<f64>().ok()? * MILE_TO_KM,
+        })
+    }
+
+    /// Whether the position is within the footprint.
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        let distance = unsafe { fap_distance(self.longitude, self.latitude, longitude, latitude) };
+        distance <= self.radius
+    }
+}
+
+/// General or directed station query.
+///
+/// General queries like `?APRS?` are sent to all stations, optionally limited to a
+/// footprint. Directed queries like `?APRSP` are sent in a message to one station.
+/// `libfap` does not parse general queries, so queries are read from the raw packet:
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::{Query, QueryType};
+///
+/// let query = Query::parse("N0CALL>APRS,WIDE1-1:?APRS? 34.02,-117.15,0200").unwrap();
+/// assert_eq!(query.kind, QueryType::Aprs);
+/// assert!(query.footprint.unwrap().contains(34.05, -117.2));
+///
+/// let query = Query::parse("N0CALL>APRS::N1CALL-10:?APRSS{3").unwrap();
+/// assert_eq!(query.kind, QueryType::Status);
+/// assert_eq!(query.addressee.as_deref(), Some("N1CALL-10"));
+/// assert!(Query::parse("N0CALL>APRS:>Status").is_none());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub kind: QueryType,
+    pub source: String,
+    /// Addressee of a directed query, `None` for general queries
+    pub addressee: Option<String>,
+    pub footprint: Option<Footprint>,
+    /// Source, destination and path of the query as received
+    pub header: String,
+}
+
+impl Query {
+    /// Reads a query from a packet in TNC2 format. Returns `None` for all other packets
+    /// and for queries with an invalid footprint.
+    pub fn parse(raw: &str) -> Option<Query> {
+        let raw = raw.trim_end_matches(['\r', '\n']);
+        let colon = raw.find(':')?;
+        let (header, body) = (&raw[..colon], &raw[colon + 1..]);
+        let source = &header[..header.find('>')?];
+        if source.is_empty() {
+            return None
+        }
+        let (kind, addressee, footprint) = if body.starts_with('?') {
+            let (query, rest) = match body.find(' ') {
+                Some(space) => (&body[..space], body[space..].trim()),
+                None => (body, ""),
+            };
+            let kind = QueryType::from_str(query).filter(QueryType::is_general)?;
+            let footprint = match rest {
+                "" => None,
+                rest => Some(Footprint::parse(rest)?),
+            };
+            (kind, None, footprint)
+        } else if body.starts_with(':') && body.get(10..11) == Some(":") {
+            let addressee = body[1..10].trim_end();
+            let text = &body[11..];
+            // the message ID, if any, is not part of the query
+            let text = text.find('{').map_or(text, |i| &text[..i]);
+            let kind = QueryType::from_str(text.split(' ').next().unwrap_or("").trim_end())?;
+            (kind, Some(addressee.to_string()), None)
+        } else {
+            return None
+        };
+        Some(Query {
+            kind,
+            source: source.to_string(),
+            addressee,
+            footprint,
+            header: header.to_string(),
+        })
+    }
+}
+
+/// Answers queries for a station.
+///
+/// Implementors give the reports the station sends; everything defaults to having
+/// nothing to report. `respond()` then decides whether a query is meant for the
+/// station and generates the replies with its `PacketBuilder`:
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::{PacketBuilder, PositionReport, Query, QueryResponder};
+///
+/// struct Digi;
+///
+/// impl QueryResponder for Digi {
+///     fn position(&self) -> Option<PositionReport> {
+///         Some(PositionReport::new(49.0583, -72.0292).symbol('/', '#'))
+///     }
+/// }
+///
+/// let builder = PacketBuilder::new("N1CALL-1");
+/// let query = Query::parse("N0CALL>APRS::N1CALL-1 :?APRST").unwrap();
+/// assert_eq!(Digi.respond(&query, &builder).unwrap(), ["N1CALL-1>APRS::N0CALL   :N0CALL>APRS"]);
+/// let query = Query::parse("N0CALL>APRS:?APRS?").unwrap();
+/// assert_eq!(Digi.respond(&query, &builder).unwrap(), ["N1CALL-1>APRS:!4903.50N/07201.75W#"]);
+/// ```
+///
+/// General queries should be answered after a random delay, so that the replies of
+/// all stations do not collide.
+pub trait QueryResponder {
+    /// Position, the reply to `?APRS?` and `?APRSP`. Also used to check footprints.
+    fn position(&self) -> Option<PositionReport> {
+        None
+    }
+
+    /// Status, the reply to `?APRSS`.
+    fn status(&self) -> Option<StatusReport> {
+        None
+    }
+
+    /// Weather, the reply to `?WX?`.
+    fn weather(&self) -> Option<WeatherReport> {
+        None
+    }
+
+    /// Number of messages and of local stations passed on, the reply to `?IGATE?`.
+    /// `None` for stations that are not IGates.
+    fn igate_counts(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    /// Objects the station reports, the reply to `?APRSO`.
+    fn objects(&self) -> Vec<ObjectReport> {
+        Vec::new()
+    }
+
+    /// Packets of the messages to `station` that are still waiting for an
+    /// acknowledgement, sent again as the reply to `?APRSM`.
+    fn pending_messages(&self, station: &str) -> Vec<String> {
+        let _ = station;
+        Vec::new()
+    }
+
+    /// Replies to `query` from the station of `builder`. Empty if the query is for
+    /// another station, from the station itself, outside of the footprint, or if
+    /// there is nothing to report.
+    fn respond(&self, query: &Query, builder: &PacketBuilder) -> Result<Vec<String>, Error> {
+        let call = builder.source();
+        if query.source.eq_ignore_ascii_case(call) {
+            return Ok(Vec::new())
+        }
+        if let Some(ref addressee) = query.addressee {
+            if !addressee.eq_ignore_ascii_case(call) {
+                return Ok(Vec::new())
+            }
+        }
+        if let Some(ref footprint) = query.footprint {
+            match self.position().map(|p| p.coordinates()) {
+                Some((lat, lon)) if footprint.contains(lat, lon) => {},
+                _ => return Ok(Vec::new()),
+            }
+        }
+        let one = |packet: Option<Result<String, Error>>| packet.into_iter().collect();
+        match query.kind {
+            QueryType::Aprs | QueryType::Position => one(self.position().map(|p| builder.position(&p))),
+            QueryType::Status => one(self.status().map(|s| builder.status(&s))),
+            QueryType::Weather => one(self.weather().map(|w| builder.weather(&w))),
+            QueryType::Igate => one(self.igate_counts().map(|(m, l)| builder.igate_capabilities(m, l))),
+            QueryType::Trace => builder.message(&Message::new(&query.source, &query.header), None),
+            QueryType::Objects => self.objects().iter().map(|o| builder.object(o)).collect(),
+            QueryType::Messages => Ok(self.pending_messages(&query.source)),
+        }
+    }
+}
@@ -0,0 +1,359 @@
+//! Last known state of stations and objects.
+//!
+//! `StationDb` keeps what the packets heard so far tell about each station and each
+//! object or item: where it is and where it has been, its status, weather and
+//! telemetry, and its PHG range. Like the rest of the crate it does no I/O and reads
+//! no clock: packets are passed in with the time they were received.
+//!
+//! ```rust
+//! extern crate fap;
+//! use fap::Packet;
+//! use fap::station::StationDb;
+//! use std::time::{Duration, UNIX_EPOCH};
+//!
+//! let mut db = StationDb::new();
+//! let now = UNIX_EPOCH + Duration::from_secs(1_500_000_000);
+//! db.ingest(&Packet::new("N1CALL-9>APRS:!4903.50N/07201.75W>Driving").unwrap(), now);
+//! db.ingest(&Packet::new("N1CALL-9>APRS:>On my way").unwrap(), now);
+//!
+//! let station = db.get("N1CALL-9").unwrap();
+//! assert!((station.position().unwrap().latitude - 49.0583333).abs() < 1e-6);
+//! assert_eq!(station.status.as_deref(), Some("On my way"));
+//! assert_eq!(db.within(49.0, -73.0, 50.0, -72.0).len(), 1);
+//! ```
+
+use aprsis::FilterContext;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use {OwnedPacket, Packet, PacketType, Parser, ScaledTelemetry, Telemetry, TelemetryDefinition, TelemetryMetadata, Weather};
+
+/// A position of a station, as reported at `time`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackPoint {
+    /// Time of the report: the packet's timestamp if it had one, the time it was
+    /// received otherwise.
+    pub time: SystemTime,
+    /// Latitude in degrees, north is positive.
+    pub latitude: f64,
+    /// Longitude in degrees, east is positive.
+    pub longitude: f64,
+    /// Altitude in meters.
+    pub altitude: Option<f64>,
+    /// Course in degrees, 0 is unknown and 360 is north.
+    pub course: Option<u32>,
+    /// Speed in km/h.
+    pub speed: Option<f64>,
+}
+
+/// What is known about a station, or an object or item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Station {
+    /// Callsign, or name of the object or item.
+    pub name: String,
+    /// Station that sent the object or item, `None` for stations.
+    pub owner: Option<String>,
+    /// When a packet about the station was last received.
+    pub last_heard: SystemTime,
+    /// Recent positions, the oldest first. Reports from where the station already
+    /// was only update the time of the last point.
+    pub track: VecDeque<TrackPoint>,
+    pub symbol_table: Option<char>,
+    pub symbol_code: Option<char>,
+    /// Comment of the last position report.
+    pub comment: Option<String>,
+    pub status: Option<String>,
+    /// Last weather report and when it was received.
+    pub weather: Option<(Weather, SystemTime)>,
+    /// Last telemetry report and when it was received.
+    pub telemetry: Option<(Telemetry, SystemTime)>,
+    /// Telemetry metadata from the `PARM.`, `UNIT.`, `EQNS.` and `BITS.` messages sent
+    /// for this station, `None` if there were none.
+    pub telemetry_metadata: Option<TelemetryMetadata>,
+    /// Raw PHG value, e.g. `5132`.
+    pub phg: Option<String>,
+    /// Radio range in kilometers, from RNG or computed from PHG.
+    pub range: Option<f64>,
+}
+
+impl Station {
+    fn new(name: &str, owner: Option<&str>, now: SystemTime) -> Station {
+        Station {
+            name: name.to_string(),
+            owner: owner.map(|o| o.to_string()),
+            last_heard: now,
+            track: VecDeque::new(),
+            symbol_table: None,
+            symbol_code: None,
+            comment: None,
+            status: None,
+            weather: None,
+            telemetry: None,
+            telemetry_metadata: None,
+            phg: None,
+            range: None,
+        }
+    }
+
+    /// Last known position.
+    pub fn position(&self) -> Option<&TrackPoint> {
+        self.track.back()
+    }
+
+    /// Whether this is an object or item rather than a station.
+    pub fn is_object(&self) -> bool {
+        self.owner.is_some()
+    }
+
+    /// Last telemetry report, scaled with the station's telemetry metadata.
+    pub fn scaled_telemetry(&self) -> Option<ScaledTelemetry> {
+        let (ref telemetry, _) = *self.telemetry.as_ref()?;
+        Some(match self.telemetry_metadata {
+            Some(ref metadata) => metadata.scale(telemetry),
+            None => TelemetryMetadata::default().scale(telemetry),
+        })
+    }
+}
+
+/// Stations and objects heard recently, by callsign or name.
+#[derive(Debug, Clone)]
+pub struct StationDb {
+    max_age: Duration,
+    track_len: usize,
+    stations: HashMap<String, Station>,
+    objects: HashMap<String, Station>,
+    // when stations and objects were heard, the oldest first, so that expiring them
+    // does not go through all of them; entries of those heard again since are stale
+    heard: VecDeque<(SystemTime, Name)>,
+}
+
+#[derive(Debug, Clone)]
+enum Name {
+    Station(String),
+    Object(String),
+}
+
+impl Default for StationDb {
+    fn default() -> StationDb {
+        StationDb::new()
+    }
+}
+
+impl StationDb {
+    /// Database forgetting stations not heard for 2 hours, with tracks of up to 100
+    /// positions.
+    pub fn new() -> StationDb {
+        StationDb {
+            max_age: Duration::from_secs(2 * 60 * 60),
+            track_len: 100,
+            stations: HashMap::new(),
+            objects: HashMap::new(),
+            heard: VecDeque::new(),
+        }
+    }
+
+    /// How long a station or object is kept after it was last heard.
+    pub fn max_age(mut self, max_age: Duration) -> StationDb {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Number of positions kept in each track, at least 1.
+    pub fn track_len(mut self, len: usize) -> StationDb {
+        self.track_len = len.max(1);
+        self
+    }
+
+    /// Updates the database with a packet received at `now`. Third-party packets
+    /// update the station that sent the packet they carry.
+    pub fn ingest(&mut self, packet: &Packet, now: SystemTime) {
+        self.ingest_with(&Parser::new(), packet, now)
+    }
+
+    /// Like `ingest()`, parsing the packet carried by a third-party packet with
+    /// `parser`, as the outer packet was.
+    pub fn ingest_with(&mut self, parser: &Parser, packet: &Packet, now: SystemTime) {
+        match parser.third_party(packet) {
+            Some(Ok(inner)) => self.ingest_with(parser, &inner, now),
+            Some(Err(_)) => self.expire(now),
+            None => self.ingest_owned(&packet.to_owned(), now),
+        }
+    }
+
+    /// Like `ingest()` for a packet already copied out of `libfap`.
+    pub fn ingest_owned(&mut self, packet: &OwnedPacket, now: SystemTime) {
+        self.expire(now);
+        if let Some((station, definition)) = telemetry_definition(packet) {
+            if !self.stations.contains_key(station) {
+                self.stations.insert(station.to_string(), Station::new(station, None, now));
+                self.index(Name::Station(station.to_string()), now);
+            }
+            self.stations.get_mut(station).unwrap().telemetry_metadata.get_or_insert_with(TelemetryMetadata::default)
+                .update(&definition);
+        }
+
+        let name = packet.object_name.as_ref().or(packet.item_name.as_ref()).map(|n| n.trim_end());
+        if name.is_some() {
+            self.stations.entry(packet.src_callsign.clone())
+                .or_insert_with(|| Station::new(&packet.src_callsign, None, now))
+                .last_heard = now;
+            self.index(Name::Station(packet.src_callsign.clone()), now);
+        }
+        let station = match name {
+            Some(name) if packet.alive == Some(false) => {
+                self.objects.remove(name);
+                return
+            },
+            Some(name) => self.objects.entry(name.to_string())
+                .or_insert_with(|| Station::new(name, Some(&packet.src_callsign), now)),
+            None => self.stations.entry(packet.src_callsign.clone())
+                .or_insert_with(|| Station::new(&packet.src_callsign, None, now)),
+        };
+        station.last_heard = now;
+        if name.is_some() {
+            station.owner = Some(packet.src_callsign.clone());
+        }
+
+        if let (Some(latitude), Some(longitude)) = (packet.latitude, packet.longitude) {
+            let time = packet.timestamp.map_or(now, |ts| UNIX_EPOCH + Duration::from_secs(ts));
+            let point = TrackPoint {
+                time,
+                latitude,
+                longitude,
+                altitude: packet.altitude,
+                course: packet.course,
+                speed: packet.speed,
+            };
+            let still = station.track.back().is_some_and(|last| (last.latitude, last.longitude) == (latitude, longitude));
+            if still {
+                station.track.pop_back();
+            }
+            station.track.push_back(point);
+            while station.track.len() > self.track_len {
+                station.track.pop_front();
+            }
+            station.comment = packet.comment.clone();
+        }
+        if packet.symbol_code.is_some() {
+            station.symbol_table = packet.symbol_table;
+            station.symbol_code = packet.symbol_code;
+        }
+        if packet.packet_type == Some(PacketType::Status) {
+            station.status = packet.status.clone();
+        }
+        if let Some(ref weather) = packet.wx_report {
+            station.weather = Some((weather.clone(), now));
+        }
+        if let Some(ref telemetry) = packet.telemetry {
+            station.telemetry = Some((telemetry.clone(), now));
+        }
+        if let Some(ref phg) = packet.phg {
+            station.phg = Some(phg.clone());
+            station.range = phg_range(phg);
+        }
+        if let Some(range) = packet.radio_range {
+            station.range = Some(range as f64);
+        }
+        let name = match name {
+            Some(name) => Name::Object(name.to_string()),
+            None => Name::Station(packet.src_callsign.clone()),
+        };
+        self.index(name, now);
+    }
+
+    /// Forgets stations and objects not heard within the maximum age. `ingest()` does
+    /// this too, call it when no packets come in for a while.
+    pub fn expire(&mut self, now: SystemTime) {
+        let max_age = self.max_age;
+        let stale = |heard: SystemTime| now.duration_since(heard).is_ok_and(|age| age > max_age);
+        while self.heard.front().is_some_and(|&(heard, _)| stale(heard)) {
+            let (map, name) = match self.heard.pop_front().unwrap().1 {
+                Name::Station(name) => (&mut self.stations, name),
+                Name::Object(name) => (&mut self.objects, name),
+            };
+            if map.get(&name).is_some_and(|station| stale(station.last_heard)) {
+                map.remove(&name);
+            }
+        }
+    }
+
+    // Records that a station or object was heard at `now`
+    fn index(&mut self, name: Name, now: SystemTime) {
+        let at = self.heard.partition_point(|&(heard, _)| heard <= now);
+        self.heard.insert(at, (now, name));
+        // drop the stale entries once they are as many as the live ones
+        if self.heard.len() > 2 * self.len() {
+            let mut heard: Vec<_> = self.stations.iter().map(|(name, s)| (s.last_heard, Name::Station(name.clone())))
+                .chain(self.objects.iter().map(|(name, s)| (s.last_heard, Name::Object(name.clone()))))
+                .collect();
+            heard.sort_by_key(|&(time, _)| time);
+            self.heard = heard.into();
+        }
+    }
+
+    /// Station by callsign.
+    pub fn get(&self, callsign: &str) -> Option<&Station> {
+        self.stations.get(callsign)
+    }
+
+    /// Object or item by name.
+    pub fn object(&self, name: &str) -> Option<&Station> {
+        self.objects.get(name)
+    }
+
+    /// Stations and objects whose last position is within the box, in degrees. A box
+    /// with `west` greater than `east` crosses the 180th meridian.
+    pub fn within(&self, south: f64, west: f64, north: f64, east: f64) -> Vec<&Station> {
+        self.iter().filter(|station| match station.position() {
+            Some(p) => {
+                let longitude = if west <= east {
+                    west <= p.longitude && p.longitude <= east
+                } else {
+                    west <= p.longitude || p.longitude <= east
+                };
+                south <= p.latitude && p.latitude <= north && longitude
+            },
+            None => false,
+        }).collect()
+    }
+
+    /// All stations, then all objects, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &Station> {
+        self.stations.values().chain(self.objects.values())
+    }
+
+    /// Number of stations and objects.
+    pub fn len(&self) -> usize {
+        self.stations.len() + self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Gives `f/` and `t/` filters the last known positions of stations.
+impl FilterContext for StationDb {
+    fn position_of(&self, callsign: &str) -> Option<(f64, f64)> {
+        let p = self.get(callsign)?.position()?;
+        Some((p.latitude, p.longitude))
+    }
+}
+
+// Range in km from `PHGphgd`, following the APRS specification:
+// sqrt(2 * height * sqrt(power / 10 * gain / 2)) miles
+fn phg_range(phg: &str) -> Option<f64> {
+    let digits: Vec<u32> = phg.chars().take(3).map(|c| c.to_digit(10)).collect::<Option<_>>()?;
+    if digits.len() < 3 {
+        return None
+    }
+    let power = (digits[0] * digits[0]) as f64;
+    let height = 10.0 * 2f64.powi(digits[1] as i32);
+    let gain = 10f64.powf(digits[2] as f64 / 10.0);
+    Some((2.0 * height * (power / 10.0 * gain / 2.0).sqrt()).sqrt() * 1.609344)
+}
+
+// Station and contents of a `PARM.`, `UNIT.`, `EQNS.` or `BITS.` message
+fn telemetry_definition(packet: &OwnedPacket) -> Option<(&str, TelemetryDefinition)> {
+    let station = packet.destination.as_ref()?.trim_end();
+    Some((station, TelemetryDefinition::parse(packet.message.as_ref()?)?))
+}
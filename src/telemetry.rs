@@ -0,0 +1,177 @@
+//! Scaling telemetry values with the metadata their stations send.
+
+use aprs::Packet as AprsPacket;
+use std::collections::HashMap;
+use {Packet, Telemetry, TelemetryDefinition};
+
+/// Everything a station told about its telemetry, gathered from its `PARM.`,
+/// `UNIT.`, `EQNS.` and `BITS.` messages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelemetryMetadata {
+    /// Names of the 5 analog channels, then of the 8 bits. May be shorter.
+    pub names: Vec<String>,
+    /// Units of the 5 analog channels, then labels of the 8 bits. May be shorter.
+    pub units: Vec<String>,
+    /// Coefficients `a`, `b` and `c` of `a * v² + b * v + c` for each analog channel.
+    pub coefficients: [(f64, f64, f64); 5],
+    /// The value of each bit that means "on", the lowest first.
+    pub bit_sense: u8,
+    /// Project title from `BITS.`.
+    pub project: Option<String>,
+}
+
+impl Default for TelemetryMetadata {
+    fn default() -> TelemetryMetadata {
+        TelemetryMetadata {
+            names: Vec::new(),
+            units: Vec::new(),
+            coefficients: [(0.0, 1.0, 0.0); 5],
+            bit_sense: 0xff,
+            project: None,
+        }
+    }
+}
+
+impl TelemetryMetadata {
+    /// Takes in one metadata message, replacing what an earlier message of the same
+    /// kind said.
+    pub fn update(&mut self, definition: &TelemetryDefinition) {
+        match *definition {
+            TelemetryDefinition::Parameters(ref names) => self.names = names.clone(),
+            TelemetryDefinition::Units(ref units) => self.units = units.clone(),
+            TelemetryDefinition::Equations(ref equations) => {
+                self.coefficients = [(0.0, 1.0, 0.0); 5];
+                for (c, e) in self.coefficients.iter_mut().zip(equations) {
+                    *c = *e;
+                }
+            },
+            TelemetryDefinition::Bits(sense, ref title) => {
+                self.bit_sense = sense;
+                self.project = if title.is_empty() { None } else { Some(title.clone()) };
+            },
+        }
+    }
+
+    /// Applies the equations, names and units to a telemetry report.
+    pub fn scale(&self, telemetry: &Telemetry) -> ScaledTelemetry {
+        let label = |labels: &[String], i: usize| labels.get(i).filter(|l| !l.is_empty()).cloned();
+        let values = telemetry.values.iter().enumerate()
+            .filter_map(|(i, v)| v.map(|v| (i, v)))
+            .map(|(i, v)| {
+                let (a, b, c) = self.coefficients[i];
+                ScaledValue {
+                    channel: i + 1,
+                    name: label(&self.names, i),
+                    unit: label(&self.units, i),
+                    value: a * v * v + b * v + c,
+                }
+            }).collect();
+        let bits = telemetry.bits.as_ref().map_or(Vec::new(), |bits| {
+            bits.chars().take(8).enumerate()
+                .filter(|&(_, bit)| bit == '0' || bit == '1')
+                .map(|(i, bit)| ScaledBit {
+                    bit: i + 1,
+                    name: label(&self.names, i + 5),
+                    label: label(&self.units, i + 5),
+                    on: (bit == '1') == (self.bit_sense >> i & 1 == 1),
+                }).collect()
+        });
+        ScaledTelemetry {
+            seq: telemetry.seq,
+            values,
+            bits,
+            project: self.project.clone(),
+        }
+    }
+}
+
+/// Telemetry report in engineering units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScaledTelemetry {
+    pub seq: Option<u32>,
+    /// Analog channels present in the report.
+    pub values: Vec<ScaledValue>,
+    /// Digital bits present in the report.
+    pub bits: Vec<ScaledBit>,
+    pub project: Option<String>,
+}
+
+/// Analog telemetry value after applying the channel's equation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScaledValue {
+    /// Channel number, 1 to 5.
+    pub channel: usize,
+    pub name: Option<String>,
+    pub unit: Option<String>,
+    pub value: f64,
+}
+
+/// Digital telemetry bit, with its sense applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScaledBit {
+    /// Bit number, 1 to 8.
+    pub bit: usize,
+    pub name: Option<String>,
+    /// Label from `UNIT.`, telling what "on" means.
+    pub label: Option<String>,
+    pub on: bool,
+}
+
+/// Telemetry metadata of many stations, for scaling their reports as they come in.
+///
+/// ```rust
+/// extern crate fap;
+/// use fap::{Packet, TelemetryRegistry};
+///
+/// let mut registry = TelemetryRegistry::new();
+/// registry.ingest(&Packet::new("SOLAR1>APRS::SOLAR1   :PARM.Vbat").unwrap());
+/// registry.ingest(&Packet::new("SOLAR1>APRS::SOLAR1   :UNIT.V").unwrap());
+/// registry.ingest(&Packet::new("SOLAR1>APRS::SOLAR1   :EQNS.0,0.1,0").unwrap());
+///
+/// let scaled = registry.ingest(&Packet::new("SOLAR1>APRS:T#001,138,0,0,0,0,00000000").unwrap()).unwrap();
+/// assert_eq!(scaled.values[0].name.as_deref(), Some("Vbat"));
+/// assert_eq!(scaled.values[0].unit.as_deref(), Some("V"));
+/// assert!((scaled.values[0].value - 13.8).abs() < 1e-9);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TelemetryRegistry {
+    stations: HashMap<String, TelemetryMetadata>,
+}
+
+impl TelemetryRegistry {
+    pub fn new() -> TelemetryRegistry {
+        TelemetryRegistry::default()
+    }
+
+    /// Records the metadata from a `PARM.`, `UNIT.`, `EQNS.` or `BITS.` message, or
+    /// scales a telemetry report. Returns the scaled report, `None` for other packets.
+    ///
+    /// Reports from stations that sent no metadata are returned unscaled.
+    pub fn ingest(&mut self, packet: &Packet) -> Option<ScaledTelemetry> {
+        if let Some(definition) = packet.telemetry_definition() {
+            let station = packet.message_destination()?;
+            self.define(station.trim_end(), &definition);
+            return None
+        }
+        let telemetry = packet.telemetry()?;
+        Some(self.scale(&packet.source(), &telemetry))
+    }
+
+    /// Records metadata for a station.
+    pub fn define(&mut self, station: &str, definition: &TelemetryDefinition) {
+        self.stations.entry(station.to_ascii_uppercase()).or_default().update(definition);
+    }
+
+    /// Scales a telemetry report from a station.
+    pub fn scale(&self, station: &str, telemetry: &Telemetry) -> ScaledTelemetry {
+        match self.metadata(station) {
+            Some(metadata) => metadata.scale(telemetry),
+            None => TelemetryMetadata::default().scale(telemetry),
+        }
+    }
+
+    /// Metadata of a station, if it sent any.
+    pub fn metadata(&self, station: &str) -> Option<&TelemetryMetadata> {
+        self.stations.get(&station.to_ascii_uppercase())
+    }
+}
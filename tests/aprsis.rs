@@ -0,0 +1,163 @@
+extern crate aprs;
+extern crate fap;
+
+use aprs::Packet;
+use fap::aprsis::{passcode, verify_passcode, Client};
+use fap::{Error, PacketType, Parser};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Accepts one connection, checks the login and sends the lines
+fn serve(listener: &TcpListener, login: &str, lines: &[&str]) -> BufReader<TcpStream> {
+    let (stream, _) = listener.accept().unwrap();
+    let mut reader = BufReader::new(stream);
+    reader.get_mut().write_all(b"# aprsc 2.1.10-gd72a17c\r\n").unwrap();
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    assert_eq!(line, format!("{}\r\n", login));
+    for line in lines {
+        reader.get_mut().write_all(format!("{}\r\n", line).as_bytes()).unwrap();
+    }
+    reader
+}
+
+fn mock_server() -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    (listener, addr)
+}
+
+#[test]
+fn login_and_packets() {
+    let (listener, addr) = mock_server();
+    let server = thread::spawn(move || {
+        let login = format!("user N0CALL-10 pass 13023 vers fap {} filter r/60.2/24.9/50", env!("CARGO_PKG_VERSION"));
+        let mut reader = serve(&listener, &login, &[
+            "# logresp N0CALL-10 verified, server T2TEST",
+            "N1CALL>APRS,TCPIP*,qAC,T2TEST:!4903.50N/07201.75W-Test",
+            "# 2.1.10-gd72a17c 19 Oct 2026 10:00:00 GMT T2TEST 127.0.0.1:14580",
+            "",
+            "N2CALL>APRS,TCPIP*,qAC,T2TEST:>Status",
+            "garbage",
+        ]);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        line
+    });
+
+    let mut client = Client::builder("N0CALL-10")
+        .server(&addr)
+        .passcode(13023)
+        .filter("r/60.2/24.9/50")
+        .build();
+    assert!(!client.is_connected());
+    let packet = client.next().unwrap().unwrap();
+    assert!(client.is_connected());
+    assert_eq!(packet.source(), "N1CALL");
+    assert_eq!(packet.comment().unwrap(), "Test");
+    let login = client.login_response().unwrap();
+    assert_eq!(login.callsign, "N0CALL-10");
+    assert!(login.verified);
+    assert_eq!(login.server.as_deref(), Some("T2TEST"));
+
+    let packet = client.next().unwrap().unwrap();
+    assert_eq!(packet.packet_type(), Some(PacketType::Status));
+    assert!(matches!(client.next(), Some(Err(Error::Other(_)))));
+    assert!(client.is_connected());
+
+    client.send("N0CALL-10>APRS,TCPIP*:>Hello").unwrap();
+    assert_eq!(server.join().unwrap(), "N0CALL-10>APRS,TCPIP*:>Hello\r\n");
+    assert!(matches!(client.next(), Some(Err(Error::Io(_)))));
+    assert!(!client.is_connected());
+    assert!(client.login_response().is_none());
+    assert!(client.send("N0CALL-10>APRS:>Hello").is_err());
+}
+
+#[test]
+fn receive_only_with_parser() {
+    let (listener, addr) = mock_server();
+    let server = thread::spawn(move || {
+        serve(&listener, "user N0CALL pass -1 vers test 1.0", &[
+            "# logresp N0CALL unverified, server T2TEST",
+            "N1CALL>APRS:>Status",
+            "N1CALL>APRS:!4903.50N/07201.75W-",
+        ])
+    });
+    let parser = Parser::builder().accept(&[PacketType::Location]).build();
+    let mut client = Client::builder("N0CALL").server(&addr).software("test", "1.0").parser(parser).build();
+    assert!(matches!(client.next(), Some(Err(Error::NotAccepted(Some(PacketType::Status))))));
+    assert!(!client.login_response().unwrap().verified);
+    assert_eq!(client.next().unwrap().unwrap().packet_type(), Some(PacketType::Location));
+    assert!(client.send("N0CALL>APRS:>Hi\r\nN0CALL>APRS:>Injected").is_err());
+    drop(server.join().unwrap());
+}
+
+#[test]
+fn reconnects() {
+    let (listener, addr) = mock_server();
+    let login = format!("user N0CALL pass -1 vers fap {}", env!("CARGO_PKG_VERSION"));
+    let server = thread::spawn(move || {
+        drop(serve(&listener, &login, &["N1CALL>APRS:>First"]));
+        drop(serve(&listener, &login, &["N1CALL>APRS:>Second"]));
+    });
+    let mut client = Client::builder("N0CALL")
+        .server(&addr)
+        .backoff(Duration::from_millis(10), Duration::from_millis(40))
+        .build();
+    assert_eq!(client.next().unwrap().unwrap().status().unwrap(), "First");
+    assert!(matches!(client.next(), Some(Err(Error::Io(_)))));
+    assert_eq!(client.next().unwrap().unwrap().status().unwrap(), "Second");
+    server.join().unwrap();
+}
+
+#[test]
+fn backoff() {
+    let (listener, addr) = mock_server();
+    drop(listener);
+    let mut client = Client::builder("N0CALL")
+        .server(&addr)
+        .backoff(Duration::from_millis(50), Duration::from_millis(100))
+        .build();
+    let start = Instant::now();
+    for _ in 0..4 {
+        assert!(matches!(client.next(), Some(Err(Error::Io(_)))));
+    }
+    // no delay before the first attempt, then 50, 100 and 100 ms
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(250), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+}
+
+#[test]
+fn silent_server() {
+    let (listener, addr) = mock_server();
+    let server = thread::spawn(move || {
+        let reader = serve(&listener, &format!("user N0CALL pass -1 vers fap {}", env!("CARGO_PKG_VERSION")), &[]);
+        thread::sleep(Duration::from_millis(500));
+        drop(reader);
+    });
+    let mut client = Client::builder("N0CALL").server(&addr).timeout(Duration::from_millis(100)).build();
+    match client.next() {
+        Some(Err(Error::Io(_))) => {},
+        other => panic!("no timeout: {:?}", other.map(|r| r.map(|p| p.body().into_owned()))),
+    }
+    assert!(!client.is_connected());
+    server.join().unwrap();
+}
+
+#[test]
+fn passcodes() {
+    assert_eq!(passcode("N0CALL"), 13023);
+    assert!(verify_passcode("N0CALL", 13023));
+    // odd lengths only mix the last character into the high byte
+    assert_eq!(passcode("K"), (0x73e2 ^ (u16::from(b'K') << 8)) & 0x7fff);
+    assert_eq!(passcode("n0call-15"), 13023);
+    assert_eq!(passcode("N0CALL-1"), passcode("N0CALL"));
+    assert_eq!(passcode(""), 0x73e2 & 0x7fff);
+    assert!(!verify_passcode("N0CALL", 13024));
+    assert!(!verify_passcode("N0CALL", -1));
+    assert!(!verify_passcode("N1CALL", 13023));
+    assert!((0..1000).all(|i| passcode(&format!("TEST{}", i)) < 0x8000));
+}
@@ -0,0 +1,108 @@
+extern crate fap;
+
+use fap::{OwnedPacket, Parser};
+use std::sync::Arc;
+use std::thread;
+
+const THREADS: usize = 16;
+const ROUNDS: usize = 50;
+
+// Raw packets of the tests/parse.rs test cases
+fn corpus() -> Vec<Vec<u8>> {
+    include_str!("parse.rs").lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.starts_with("raw: b\"") && line.ends_with("\",") {
+                Some(unescape(&line[7..line.len() - 2]))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn unescape(s: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        if b != b'\\' {
+            out.push(b);
+            continue;
+        }
+        match bytes.next() {
+            Some(b'x') => {
+                let hex: String = bytes.by_ref().take(2).map(|b| b as char).collect();
+                out.push(u8::from_str_radix(&hex, 16).unwrap());
+            }
+            Some(b'n') => out.push(b'\n'),
+            Some(b'r') => out.push(b'\r'),
+            Some(b't') => out.push(b'\t'),
+            Some(other) => out.push(other),
+            None => break,
+        }
+    }
+    out
+}
+
+fn parse_all(parser: &Parser, corpus: &[Vec<u8>]) -> Vec<Option<OwnedPacket>> {
+    corpus.iter()
+        .map(|raw| parser.parse(raw.clone()).ok().map(|p| p.to_owned()))
+        .collect()
+}
+
+#[test]
+fn corpus_is_loaded() {
+    assert!(corpus().len() > 40);
+}
+
+#[test]
+fn parse_from_many_threads() {
+    let corpus = Arc::new(corpus());
+    let parser = Arc::new(Parser::new());
+    let expected = parse_all(&parser, &corpus);
+
+    let handles: Vec<_> = (0..THREADS).map(|_| {
+        let corpus = corpus.clone();
+        let parser = parser.clone();
+        thread::spawn(move || {
+            (0..ROUNDS).map(|_| parse_all(&parser, &corpus)).collect::<Vec<_>>()
+        })
+    }).collect();
+
+    for handle in handles {
+        for parsed in handle.join().unwrap() {
+            assert_eq!(parsed, expected);
+        }
+    }
+}
+
+#[test]
+fn parsers_created_and_dropped_concurrently() {
+    let corpus = Arc::new(corpus());
+    let handles: Vec<_> = (0..THREADS).map(|_| {
+        let corpus = corpus.clone();
+        thread::spawn(move || {
+            for _ in 0..ROUNDS {
+                let parser = Parser::new();
+                for raw in corpus.iter() {
+                    let _ = parser.parse(raw.clone());
+                }
+            }
+        })
+    }).collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+#[test]
+fn packet_outlives_parser() {
+    let parser = Parser::new();
+    let packet = parser.parse("N0CALL>APRS:>Hello").unwrap();
+    drop(parser);
+    assert_eq!(packet.status().unwrap(), "Hello");
+
+    // libfap is initialized again by the next parser
+    let parser = Parser::new();
+    assert!(parser.parse("N0CALL>APRS:>Hello again").is_ok());
+}
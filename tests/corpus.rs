@@ -0,0 +1,128 @@
+//! Data-driven parser tests.
+//!
+//! Every line of `tests/corpus/*.jsonl` is a JSON object holding a raw packet and the
+//! fields Ham::APRS::FAP reports for it, using the same keys as the `serde` feature.
+//! Records marked with `"ignore": true` are known differences between libfap and Perl;
+//! `"note"` documents differences against the Python aprslib where there are any.
+//! Packets are parsed as if received at `"now"`, or at `REFERENCE_TIME` when there is
+//! no timestamp to resolve.
+//! Perl leaves out `posambiguity` and `messaging` when they are zero or false, so a
+//! missing value there matches a zero or missing one from libfap.
+
+extern crate fap;
+extern crate serde_json;
+
+use fap::{CommentMode, OwnedPacket, Parser};
+use serde_json::Value;
+use std::time::{Duration, UNIX_EPOCH};
+
+const EPSILON: f64 = 0.0001;
+// Shortly after the last corpus packet was heard
+const REFERENCE_TIME: u64 = 1528600000;
+
+fn corpus() -> Vec<Value> {
+    include_str!("corpus/parse.jsonl").lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).expect("bad corpus line"))
+        .collect()
+}
+
+fn parse(record: &Value) -> Result<OwnedPacket, fap::Error> {
+    let now = record["now"].as_u64().unwrap_or(REFERENCE_TIME);
+    Parser::builder()
+        .comment_mode(CommentMode::PerlCompatible)
+        .reference_time(UNIX_EPOCH + Duration::from_secs(now))
+        .build()
+        .parse(record["raw"].as_str().unwrap())
+        .map(|p| p.to_owned())
+}
+
+fn check_str(errors: &mut Vec<String>, key: &str, expected: &Value, actual: Option<&str>) {
+    if expected.as_str() != actual {
+        errors.push(format!("{}: expected {}, got {:?}", key, expected, actual));
+    }
+}
+
+fn check_num(errors: &mut Vec<String>, key: &str, expected: &Value, actual: Option<f64>) {
+    let ok = match (expected.as_f64(), actual) {
+        (None, None) => true,
+        (Some(e), Some(a)) => (e - a).abs() < EPSILON,
+        _ => false,
+    };
+    if !ok {
+        errors.push(format!("{}: expected {}, got {:?}", key, expected, actual));
+    }
+}
+
+fn or_zero(value: &Value) -> Value {
+    match *value {
+        Value::Null => Value::from(0),
+        ref v => v.clone(),
+    }
+}
+
+fn check(record: &Value, packet: &OwnedPacket) -> Vec<String> {
+    let mut errors = Vec::new();
+    let e = &mut errors;
+    check_str(e, "type", &record["type"], packet.packet_type.map(|t| t.as_str()));
+    check_str(e, "format", &record["format"], packet.format.map(|f| f.as_str()));
+    check_str(e, "srccallsign", &record["srccallsign"], Some(packet.src_callsign.as_str()));
+    check_str(e, "dstcallsign", &record["dstcallsign"], packet.dst_callsign.as_deref());
+
+    let digis: Vec<(&str, bool)> = record["digipeaters"].as_array().unwrap().iter()
+        .map(|d| (d["call"].as_str().unwrap(), d["wasdigied"].as_bool().unwrap()))
+        .collect();
+    let actual: Vec<(&str, bool)> = packet.digipeaters.iter()
+        .map(|d| (d.call.as_str(), d.digipeated))
+        .collect();
+    if digis != actual {
+        e.push(format!("digipeaters: expected {:?}, got {:?}", digis, actual));
+    }
+
+    check_num(e, "timestamp", &record["timestamp"], packet.timestamp.map(|v| v as f64));
+    check_num(e, "latitude", &record["latitude"], packet.latitude);
+    check_num(e, "longitude", &record["longitude"], packet.longitude);
+    check_num(e, "posambiguity", &or_zero(&record["posambiguity"]), Some(f64::from(packet.pos_ambiguity.unwrap_or(0))));
+    check_num(e, "posresolution", &record["posresolution"], packet.pos_resolution);
+    check_num(e, "messaging", &or_zero(&record["messaging"]), Some(if packet.messaging == Some(true) { 1.0 } else { 0.0 }));
+    check_str(e, "symboltable", &record["symboltable"], packet.symbol_table.map(|c| c.to_string()).as_deref());
+    check_str(e, "symbolcode", &record["symbolcode"], packet.symbol_code.map(|c| c.to_string()).as_deref());
+    check_num(e, "course", &record["course"], packet.course.map(f64::from));
+    check_num(e, "speed", &record["speed"], packet.speed);
+    check_num(e, "altitude", &record["altitude"], packet.altitude);
+    check_str(e, "comment", &record["comment"], packet.comment.as_deref());
+    errors
+}
+
+fn run(ignored: bool) {
+    let mut failures = Vec::new();
+    let mut checked = 0;
+    for record in corpus() {
+        if record["ignore"].as_bool().unwrap_or(false) != ignored {
+            continue;
+        }
+        let raw = record["raw"].as_str().unwrap();
+        match parse(&record) {
+            Ok(packet) => {
+                for err in check(&record, &packet) {
+                    failures.push(format!("{:?}: {}", raw, err));
+                }
+            }
+            Err(err) => failures.push(format!("{:?}: parse error: {}", raw, err)),
+        }
+        checked += 1;
+    }
+    assert!(checked > 0, "empty corpus");
+    assert!(failures.is_empty(), "{} mismatches:\n{}", failures.len(), failures.join("\n"));
+}
+
+#[test]
+fn corpus_matches_perl() {
+    run(false);
+}
+
+#[test]
+#[ignore]
+fn corpus_known_differences() {
+    run(true);
+}
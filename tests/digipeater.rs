@@ -0,0 +1,117 @@
+extern crate fap;
+
+use fap::digipeater::Digi;
+use fap::{tnc2_to_ax25, Packet};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(1_500_000_000 + secs)
+}
+
+fn digipeat(digi: &mut Digi, raw: &str) -> Option<String> {
+    digi.digipeat(&Packet::new(raw).unwrap(), at(0))
+}
+
+#[test]
+fn wide() {
+    let repeated = [
+        ("N1CALL>APRS,WIDE1-1,WIDE2-1:>Hi", "N1CALL>APRS,N0CALL-2*,WIDE1*,WIDE2-1:>Hi"),
+        ("N1CALL>APRS,WIDE2-2:>Hi", "N1CALL>APRS,N0CALL-2*,WIDE2-1:>Hi"),
+        ("N1CALL>APRS,N0CALL-3*,WIDE2-1:>Hi", "N1CALL>APRS,N0CALL-3*,N0CALL-2*,WIDE2*:>Hi"),
+        ("N1CALL>APRS,N0CALL-3*,WIDE1*,WIDE2-1:>Hi", "N1CALL>APRS,N0CALL-3*,WIDE1*,N0CALL-2*,WIDE2*:>Hi"),
+        // directly to our callsign or alias
+        ("N1CALL>APRS,N0CALL-2,WIDE2-2:>Hi", "N1CALL>APRS,N0CALL-2*,WIDE2-2:>Hi"),
+        ("N1CALL>APRS,RELAY*,CLUB:>Hi", "N1CALL>APRS,RELAY*,N0CALL-2*:>Hi"),
+        // no room for our callsign
+        ("N1CALL>APRS,WIDE1-1,D2,D3,D4,D5,D6,D7,D8:>Hi", "N1CALL>APRS,WIDE1*,D2,D3,D4,D5,D6,D7,D8:>Hi"),
+    ];
+    for &(raw, expected) in &repeated {
+        let mut digi = Digi::new("n0call-2").aliases(&["CLUB"]);
+        assert_eq!(digipeat(&mut digi, raw).as_deref(), Some(expected), "{}", raw);
+    }
+
+    let ignored = [
+        "N1CALL>APRS:>Hi",
+        "N1CALL>APRS,WIDE1*:>Hi",
+        "N1CALL>APRS,N0CALL-3,WIDE2-2:>Hi",
+        "N1CALL>APRS,N0CALL-2*,WIDE2-1:>Hi",
+        "N0CALL-2>APRS,WIDE2-2:>Hi",
+        "N1CALL>APRS,WIDE1-2:>Hi",
+        "N1CALL>APRS,WIDE4-4:>Hi",
+        "N1CALL>APRS,WIDE2-2,WIDE2-2:>Hi",
+        "N1CALL>APRS,N0CALL-3*,WIDE1*,N0CALL-4*,WIDE3-1,WIDE2-1:>Hi",
+        "N1CALL>APRS,TCPIP*,WIDE2-2:>Hi",
+        "N1CALL>APRS,WIDE2-2,qAR,N0CALL-10:>Hi",
+        // libfap can not count the hops past an invalid callsign
+        "N1CALL>APRS,N0CALL-16*,WIDE2-1:>Hi",
+        "N1CALL>APRS,N0CALL-16,WIDE2-2:>Hi",
+        "N1CALL>APRS,wide3-3:>Hi",
+    ];
+    for raw in &ignored {
+        let mut digi = Digi::new("N0CALL-2").aliases(&["CLUB"]);
+        assert_eq!(digipeat(&mut digi, raw), None, "{}", raw);
+    }
+
+    let mut digi = Digi::new("N0CALL-2").max_hops(4);
+    assert!(digipeat(&mut digi, "N1CALL>APRS,WIDE4-4:>Hi").is_some());
+
+    // the rewritten path converts to AX.25 with the H-bits set
+    let mut digi = Digi::new("N0CALL-2");
+    let line = digipeat(&mut digi, "N1CALL>APRS,WIDE1-1,WIDE2-1:>Hi").unwrap();
+    let frame = tnc2_to_ax25(line.as_bytes()).unwrap();
+    let flags: Vec<bool> = frame[14..].chunks(7).take(3).map(|call| call[6] & 0x80 != 0).collect();
+    assert_eq!(flags, [true, true, false]);
+}
+
+#[test]
+fn fill_in() {
+    let mut digi = Digi::new("N0CALL-2").fill_in(true);
+    assert_eq!(digipeat(&mut digi, "N1CALL>APRS,WIDE1-1,WIDE2-1:>Hi").as_deref(),
+               Some("N1CALL>APRS,N0CALL-2*,WIDE1*,WIDE2-1:>Hi"));
+    assert_eq!(digipeat(&mut digi, "N2CALL>APRS,WIDE2-2:>Hi"), None);
+    assert_eq!(digipeat(&mut digi, "N2CALL>APRS,N0CALL-3*,WIDE1*,WIDE2-1:>Hi"), None);
+    assert!(digipeat(&mut digi, "N2CALL>APRS,N0CALL-2:>Hi").is_some());
+}
+
+#[test]
+fn preemptive() {
+    let raw = "N1CALL>APRS,N0CALL-3,N0CALL-2,WIDE2-1:>Hi";
+    assert_eq!(digipeat(&mut Digi::new("N0CALL-2"), raw), None);
+    assert_eq!(digipeat(&mut Digi::new("N0CALL-2").preemptive(true), raw).as_deref(),
+               Some("N1CALL>APRS,N0CALL-3*,N0CALL-2*,WIDE2-1:>Hi"));
+    // generic aliases are not preempted
+    assert_eq!(digipeat(&mut Digi::new("N0CALL-2").preemptive(true), "N1CALL>APRS,N0CALL-3,WIDE2-1:>Hi"), None);
+}
+
+#[test]
+fn duplicates() {
+    let mut digi = Digi::new("N0CALL-2").dupe_window(Duration::from_secs(20));
+    let packet = Packet::new("N1CALL>APRS,WIDE2-2:!4903.50N/07201.75W-").unwrap();
+    assert!(digi.digipeat(&packet, at(0)).is_some());
+    // our own transmission, or another digipeater's, heard back
+    let heard = Packet::new("N1CALL>APRS,N0CALL-2*,WIDE2-1:!4903.50N/07201.75W-").unwrap();
+    assert!(digi.digipeat(&heard, at(1)).is_none());
+    assert!(digi.digipeat(&packet, at(20)).is_none());
+    assert!(digi.digipeat(&packet, at(21)).is_some());
+    // other stations and other content are not duplicates
+    assert!(digi.digipeat(&Packet::new("N2CALL>APRS,WIDE2-2:!4903.50N/07201.75W-").unwrap(), at(22)).is_some());
+    assert!(digi.digipeat(&Packet::new("N1CALL>APRS,WIDE2-2:!4903.51N/07201.75W-").unwrap(), at(22)).is_some());
+}
+
+#[test]
+fn viscous() {
+    let mut digi = Digi::new("N0CALL-2").viscous(Duration::from_secs(5));
+    assert_eq!(digi.next_due(), None);
+    assert_eq!(digi.digipeat(&Packet::new("N1CALL>APRS,WIDE1-1:>Hi").unwrap(), at(0)), None);
+    assert_eq!(digi.digipeat(&Packet::new("N2CALL>APRS,WIDE1-1:>Hi").unwrap(), at(1)), None);
+    // to our callsign, repeated right away
+    assert!(digi.digipeat(&Packet::new("N3CALL>APRS,N0CALL-2:>Hi").unwrap(), at(1)).is_some());
+    assert_eq!(digi.next_due(), Some(at(5)));
+
+    // another digipeater got N2CALL first
+    assert_eq!(digi.digipeat(&Packet::new("N2CALL>APRS,N0CALL-3*,WIDE1*:>Hi").unwrap(), at(2)), None);
+    assert!(digi.poll(at(4)).is_empty());
+    assert_eq!(digi.poll(at(5)), ["N1CALL>APRS,N0CALL-2*,WIDE1*:>Hi"]);
+    assert!(digi.poll(at(10)).is_empty());
+    assert_eq!(digi.next_due(), None);
+}
@@ -0,0 +1,65 @@
+extern crate fap;
+
+use fap::{DupeChecker, Packet};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(1_500_000_000 + secs)
+}
+
+#[test]
+fn paths() {
+    let mut dupes = DupeChecker::new(Duration::from_secs(30));
+    assert!(dupes.is_empty());
+    let copies = [
+        "N1CALL>APRS,WIDE1-1,WIDE2-1:!4903.50N/07201.75W-Hi",
+        "N1CALL>APRS,DIGI1*,WIDE1*,WIDE2-1,qAR,N0CALL-10:!4903.50N/07201.75W-Hi",
+        "N1CALL>APRS,TCPIP*,qAC,T2TEST:!4903.50N/07201.75W-Hi  ",
+        "N1CALL>APRS:!4903.50N/07201.75W-Hi\r\n",
+    ];
+    assert!(!dupes.is_dupe(&Packet::new(copies[0]).unwrap(), at(0)));
+    for raw in &copies[1..] {
+        assert!(dupes.is_dupe(&Packet::new(*raw).unwrap(), at(1)), "{}", raw);
+        assert!(dupes.is_dupe_tnc2(raw, at(1)), "{}", raw);
+    }
+    let others = [
+        "N2CALL>APRS:!4903.50N/07201.75W-Hi",
+        "N1CALL-1>APRS:!4903.50N/07201.75W-Hi",
+        "N1CALL>APZ001:!4903.50N/07201.75W-Hi",
+        "N1CALL>APRS:!4903.50N/07201.75W-Hi!",
+    ];
+    for raw in &others {
+        assert!(!dupes.is_dupe(&Packet::new(*raw).unwrap(), at(2)), "{}", raw);
+    }
+    assert_eq!(dupes.len(), 5);
+    assert!(!dupes.is_dupe_tnc2("not a packet", at(2)));
+    assert!(!dupes.is_dupe_tnc2("not a packet", at(2)));
+
+    dupes.clear();
+    assert!(!dupes.is_dupe_tnc2(copies[0], at(3)));
+}
+
+#[test]
+fn window() {
+    let mut dupes = DupeChecker::new(Duration::from_secs(30));
+    let packet = Packet::new("N1CALL>APRS:>Status").unwrap();
+    assert!(!dupes.is_dupe(&packet, at(0)));
+    assert!(dupes.is_dupe(&packet, at(30)));
+    // the window runs from the first copy
+    assert!(!dupes.is_dupe(&packet, at(31)));
+    assert!(!dupes.is_dupe_tnc2("N2CALL>APRS:>Status", at(40)));
+    assert_eq!(dupes.len(), 2);
+    assert!(!dupes.is_dupe(&packet, at(100)));
+    assert_eq!(dupes.len(), 1);
+}
+
+#[test]
+fn capacity() {
+    let mut dupes = DupeChecker::new(Duration::from_secs(30)).capacity(2);
+    for call in &["N1CALL", "N2CALL", "N3CALL"] {
+        assert!(!dupes.is_dupe_tnc2(&format!("{}>APRS:>Status", call), at(0)));
+    }
+    assert_eq!(dupes.len(), 2);
+    assert!(dupes.is_dupe_tnc2("N3CALL>APRS:>Status", at(1)));
+    assert!(!dupes.is_dupe_tnc2("N1CALL>APRS:>Status", at(1)));
+}
@@ -0,0 +1,159 @@
+#[macro_use] extern crate approx;
+extern crate fap;
+
+use fap::{Error, OwnedPacket, PacketBuilder, PacketType, Parser, Phg, PositionFormat, PositionReport, Timestamp};
+use std::time::{Duration, UNIX_EPOCH};
+
+fn round_trip(report: &PositionReport) -> OwnedPacket {
+    let raw = PacketBuilder::new("N0CALL").path(&["WIDE1-1"]).position(report).unwrap();
+    let packet = OwnedPacket::new(raw.as_str()).unwrap_or_else(|e| panic!("{}: {}", raw, e));
+    assert_eq!(packet.packet_type, Some(PacketType::Location), "{}", raw);
+    packet
+}
+
+#[test]
+fn uncompressed() {
+    let report = PositionReport::new(49.0583, -72.0292)
+        .symbol('/', '>')
+        .messaging(true)
+        .course_speed(88, 66.7)
+        .altitude(234.7)
+        .comment("Test");
+    assert_eq!(PacketBuilder::new("N0CALL").position(&report).unwrap(),
+               "N0CALL>APRS:=4903.50N/07201.75W>088/036/A=000770Test");
+    let packet = round_trip(&report);
+    assert_eq!(packet.format, Some(PositionFormat::Uncompressed));
+    assert_abs_diff_eq!(packet.latitude.unwrap(), 49.0583, epsilon = 0.0001);
+    assert_abs_diff_eq!(packet.longitude.unwrap(), -72.0292, epsilon = 0.0001);
+    assert_eq!(packet.symbol_table, Some('/'));
+    assert_eq!(packet.symbol_code, Some('>'));
+    assert_eq!(packet.messaging, Some(true));
+    assert_eq!(packet.course, Some(88));
+    assert_abs_diff_eq!(packet.speed.unwrap(), 36.0 * 1.852, epsilon = 0.0001);
+    assert_abs_diff_eq!(packet.altitude.unwrap(), 770.0 * 0.3048, epsilon = 0.0001);
+    assert_eq!(packet.comment.as_deref(), Some("Test"));
+
+    let packet = round_trip(&PositionReport::new(-33.8688, 151.2093).symbol('S', '#'));
+    assert_abs_diff_eq!(packet.latitude.unwrap(), -33.8688, epsilon = 0.0001);
+    assert_abs_diff_eq!(packet.longitude.unwrap(), 151.2093, epsilon = 0.0001);
+    assert_eq!(packet.symbol_table, Some('S'));
+    assert_eq!(packet.messaging, Some(false));
+    assert_eq!(packet.comment, None);
+
+    let packet = round_trip(&PositionReport::new(10.0, 10.0).altitude(-10.0));
+    assert_abs_diff_eq!(packet.altitude.unwrap(), -33.0 * 0.3048, epsilon = 0.0001);
+}
+
+#[test]
+fn timestamps() {
+    let time = UNIX_EPOCH + Duration::from_secs(1528597526);
+    let report = PositionReport::new(40.7733, -119.202).timestamp(Timestamp::Hms(time));
+    let raw = PacketBuilder::new("N0CALL").position(&report).unwrap();
+    assert_eq!(raw, "N0CALL>APRS:/022526h4046.40N/11912.12W/");
+    let parser = Parser::builder().reference_time(time + Duration::from_secs(60)).build();
+    assert_eq!(parser.parse(raw).unwrap().to_owned().timestamp, Some(1528597526));
+
+    let report = report.timestamp(Timestamp::Dhm(time)).messaging(true);
+    let raw = PacketBuilder::new("N0CALL").position(&report).unwrap();
+    assert_eq!(raw, "N0CALL>APRS:@100225z4046.40N/11912.12W/");
+    assert_eq!(parser.parse(raw).unwrap().to_owned().timestamp, Some(1528597500));
+
+    let early = PositionReport::new(40.7733, -119.202).timestamp(Timestamp::Dhm(UNIX_EPOCH - Duration::from_secs(1)));
+    match PacketBuilder::new("N0CALL").position(&early) {
+        Err(Error::Encode(_)) => {},
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn ambiguity_and_dao() {
+    for ambiguity in 1..5 {
+        let packet = round_trip(&PositionReport::new(49.0583, -72.0292).ambiguity(ambiguity));
+        assert_eq!(packet.pos_ambiguity, Some(u32::from(ambiguity)));
+    }
+    assert_eq!(PacketBuilder::new("N0CALL").position(&PositionReport::new(49.0583, -72.0292).ambiguity(3)).unwrap(),
+               "N0CALL>APRS:!490 .  N/0720 .  W/");
+
+    let packet = round_trip(&PositionReport::new(49.05831234, -72.02921234).dao(true).comment("x"));
+    assert_eq!(packet.dao_datum_byte, Some('W'));
+    assert_abs_diff_eq!(packet.latitude.unwrap(), 49.05831234, epsilon = 0.000002);
+    assert_abs_diff_eq!(packet.longitude.unwrap(), -72.02921234, epsilon = 0.000002);
+    assert_eq!(packet.comment.as_deref(), Some("x"));
+}
+
+#[test]
+fn compressed() {
+    let base = PositionReport::new(49.5, -72.75).symbol('/', '>').compressed(true);
+    let raw = PacketBuilder::new("N0CALL").position(&base).unwrap();
+    assert_eq!(raw, "N0CALL>APRS:!/5L!!<*e7> sT");
+    let packet = round_trip(&base);
+    assert_eq!(packet.format, Some(PositionFormat::Compressed));
+    assert_abs_diff_eq!(packet.latitude.unwrap(), 49.5, epsilon = 0.0001);
+    assert_abs_diff_eq!(packet.longitude.unwrap(), -72.75, epsilon = 0.0001);
+    assert_eq!(packet.course, None);
+
+    let packet = round_trip(&base.clone().course_speed(88, 66.7).altitude(1000.0).comment("Test"));
+    assert_eq!(packet.course, Some(88));
+    assert_abs_diff_eq!(packet.speed.unwrap(), 66.7, epsilon = 66.7 * 0.04);
+    assert_abs_diff_eq!(packet.altitude.unwrap(), 1000.0, epsilon = 0.2);
+    assert_eq!(packet.comment.as_deref(), Some("Test"));
+
+    let packet = round_trip(&base.clone().altitude(1000.0));
+    assert_eq!(packet.course, None);
+    assert_abs_diff_eq!(packet.altitude.unwrap(), 1000.0, epsilon = 1000.0 * 0.002);
+
+    let packet = round_trip(&base.clone().range(32.0));
+    assert_abs_diff_eq!(f64::from(packet.radio_range.unwrap()), 32.0, epsilon = 32.0 * 0.08);
+
+    let packet = round_trip(&base.clone().symbol('3', '#'));
+    assert_eq!(packet.symbol_table, Some('3'));
+}
+
+#[test]
+fn phg_and_range() {
+    let phg = Phg { power: 25.0, height: 100.0, gain: 6.0, directivity: Some(90) };
+    let packet = round_trip(&PositionReport::new(49.0583, -72.0292).symbol('/', '#').phg(phg).comment("Digi"));
+    assert_eq!(packet.phg.as_deref(), Some("5562"));
+    assert_eq!(packet.comment.as_deref(), Some("Digi"));
+
+    let packet = round_trip(&PositionReport::new(49.0583, -72.0292).compressed(true).course_speed(90, 10.0).phg(phg));
+    assert_eq!(packet.phg.as_deref(), Some("5562"));
+    assert_eq!(packet.course, Some(92));
+
+    let packet = round_trip(&PositionReport::new(49.0583, -72.0292).range(50.0));
+    assert_eq!(packet.radio_range, Some(49));
+}
+
+#[test]
+fn invalid_reports() {
+    let builder = PacketBuilder::new("N0CALL");
+    let base = PositionReport::new(49.0583, -72.0292);
+    let rejected = |report: PositionReport| match builder.position(&report) {
+        Err(Error::Encode(_)) => {},
+        other => panic!("{:?} not rejected: {:?}", report, other),
+    };
+    rejected(PositionReport::new(91.0, 0.0));
+    rejected(PositionReport::new(0.0, f64::NAN));
+    rejected(base.clone().symbol('x', '>'));
+    rejected(base.clone().ambiguity(5));
+    rejected(base.clone().ambiguity(2).dao(true));
+    rejected(base.clone().compressed(true).ambiguity(1));
+    rejected(base.clone().course_speed(361, 10.0));
+    rejected(base.clone().course_speed(90, 10.0).range(10.0));
+    rejected(base.clone().comment("two\nlines"));
+
+    assert!(PacketBuilder::new("n0call").position(&base).is_err());
+    assert!(PacketBuilder::new("N0CALL").path(&["WIDE1,1"]).position(&base).is_err());
+    assert!(PacketBuilder::new("N0CALL").path(&["qAR", "igate*"]).position(&base).is_ok());
+}
+
+#[test]
+fn third_party() {
+    let builder = PacketBuilder::new("N0CALL-10").path(&["WIDE1-1"]);
+    let raw = builder.third_party("N1CALL>APRS,TCPIP*,qAC,T2TEST::N2CALL   :Hi{1").unwrap();
+    assert_eq!(raw, "N0CALL-10>APRS,WIDE1-1:}N1CALL>APRS,TCPIP,N0CALL-10*::N2CALL   :Hi{1");
+    assert!(builder.third_party("N1CALL>APRS").is_err());
+    assert!(builder.third_party(">APRS:>Hi").is_err());
+    assert!(builder.third_party("N1CALL>:>Hi").is_err());
+    assert!(builder.third_party("N1CALL>APRS:>Hi\r\nN0CALL>APRS:>Injected").is_err());
+}
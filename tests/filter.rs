@@ -0,0 +1,144 @@
+extern crate fap;
+
+use fap::aprsis::{Filter, FilterContext};
+use fap::Packet;
+
+fn packet(raw: &str) -> Packet {
+    Packet::new(raw).unwrap_or_else(|e| panic!("{}: {}", raw, e))
+}
+
+fn check(filter: &str, passed: &[&str], blocked: &[&str]) {
+    let filter = Filter::parse(filter).unwrap();
+    for raw in passed {
+        assert!(filter.matches(&packet(raw)), "{} blocked {}", filter, raw);
+    }
+    for raw in blocked {
+        assert!(!filter.matches(&packet(raw)), "{} passed {}", filter, raw);
+    }
+}
+
+const POSITION: &str = "N1CALL-9>APRS,WIDE1-1,qAR,IGATE:!4903.50N/07201.75W>";
+const OBJECT: &str = "N1CALL>APRS,qAC,T2TEST:;LEADER   *092345z4903.50N/07201.75W>088/036";
+const ITEM: &str = "N1CALL>APRS,qAC,T2TEST:)AID #2!4903.50N\\07201.75WA";
+const MESSAGE: &str = "N1CALL>APRS,TCPIP*,qAC,T2TEST::N2CALL-7 :Hello{01";
+const BULLETIN: &str = "N1CALL>APRS,qAC,T2TEST::BLN1     :Net tonight";
+const NWS: &str = "N1CALL>APRS,qAC,T2TEST::NWS-WARN :Tornado warning";
+const STATUS: &str = "N2CALL>APZ123,DIGI1*,DIGI2*,WIDE2-1,qAo,IGATE2:>Status";
+const TELEMETRY: &str = "N2CALL>APRS,qAC,T2TEST:T#042,13.8,4.25,120,000,999,11000001";
+const WEATHER: &str = "N3CALL>APRS,qAC,T2TEST:_06100225c220s009g021t010r005p100P047h00b10133";
+const POSITION_WX: &str = "N3CALL>APRS,qAC,T2TEST:!4903.50N/07201.75W_220/009g021t010";
+const FAR: &str = "OH2XYZ>APRS,qAR,OH2RDP:!6010.00N/02450.00E-";
+const OVERLAY: &str = "OH2XYZ>APRS,qAR,OH2RDP:!6010.00NS02450.00E#";
+const UNPROTO: &str = "N4CALL>BEACON,qAR,IGATE:!4903.50N/07201.75W-";
+
+#[test]
+fn range_and_area() {
+    check("r/49.06/-72.03/10", &[POSITION, OBJECT, ITEM, POSITION_WX], &[FAR, MESSAGE, STATUS, WEATHER]);
+    check("r/49.06/-72.03/10 r/60.17/24.83/1", &[POSITION, FAR], &[MESSAGE]);
+    check("r/60/25/50", &[FAR], &[POSITION]);
+    check("a/50/-73/48/-71", &[POSITION, OBJECT], &[FAR, MESSAGE]);
+    check("a/61/24/60/25", &[FAR], &[POSITION]);
+}
+
+#[test]
+fn callsigns() {
+    check("p/N1/OH", &[POSITION, OBJECT, FAR], &[STATUS, WEATHER]);
+    check("b/N1CALL-9/n2call", &[POSITION, STATUS, TELEMETRY], &[OBJECT, FAR]);
+    check("b/N1*", &[POSITION, OBJECT, MESSAGE], &[STATUS]);
+    check("o/LEADER/AID*", &[OBJECT, ITEM], &[POSITION]);
+    check("o/LEAD", &[], &[OBJECT]);
+    check("g/N2CALL*", &[MESSAGE], &[BULLETIN, POSITION]);
+    check("g/BLN*", &[BULLETIN], &[MESSAGE]);
+    check("u/APZ*/BEACON", &[STATUS, UNPROTO], &[POSITION]);
+}
+
+#[test]
+fn types() {
+    check("t/p", &[POSITION, FAR, POSITION_WX], &[OBJECT, ITEM, MESSAGE, STATUS, WEATHER]);
+    check("t/oi", &[OBJECT, ITEM], &[POSITION]);
+    check("t/m", &[MESSAGE, BULLETIN, NWS], &[STATUS]);
+    check("t/n", &[NWS], &[MESSAGE, BULLETIN]);
+    check("t/s", &[STATUS], &[MESSAGE]);
+    check("t/t", &[TELEMETRY], &[STATUS]);
+    check("t/w", &[WEATHER, POSITION_WX], &[POSITION, TELEMETRY]);
+    check("t/PW", &[POSITION, WEATHER], &[MESSAGE]);
+}
+
+#[test]
+fn symbols() {
+    check("s/>", &[POSITION, OBJECT], &[ITEM, FAR, OVERLAY]);
+    check("s//A#", &[ITEM, OVERLAY], &[POSITION]);
+    check("s//#/S", &[OVERLAY], &[ITEM]);
+    check("s/-/A", &[FAR, ITEM], &[POSITION]);
+}
+
+#[test]
+fn path() {
+    check("d/DIGI2", &[STATUS], &[POSITION]);
+    check("d/DIGI*", &[STATUS], &[POSITION]);
+    check("d/WIDE2-1", &[], &[STATUS]);
+    check("d/TCPIP", &[MESSAGE], &[POSITION]);
+    check("e/IGATE*/OH2RDP", &[POSITION, STATUS, FAR], &[MESSAGE]);
+    check("e/QAR", &[], &[POSITION]);
+    check("q/R", &[POSITION, FAR], &[MESSAGE, STATUS]);
+    check("q/Co", &[MESSAGE, STATUS], &[POSITION]);
+    check("q/c", &[], &[MESSAGE]);
+}
+
+#[test]
+fn exclusions() {
+    check("t/p -p/OH", &[POSITION], &[FAR]);
+    check("-b/N1CALL", &[], &[POSITION, STATUS]);
+    check("t/pm -t/m -r/60/25/100", &[POSITION], &[MESSAGE, FAR]);
+    check("", &[], &[POSITION]);
+}
+
+struct Context;
+
+impl FilterContext for Context {
+    fn my_position(&self) -> Option<(f64, f64)> {
+        Some((60.2, 24.9))
+    }
+
+    fn position_of(&self, callsign: &str) -> Option<(f64, f64)> {
+        match callsign {
+            "N1CALL-9" => Some((49.06, -72.03)),
+            _ => None,
+        }
+    }
+
+    fn is_igate(&self, callsign: &str) -> bool {
+        callsign == "N1CALL-9"
+    }
+}
+
+#[test]
+fn context() {
+    let matches = |filter: &str, raw: &str| {
+        let filter = Filter::parse(filter).unwrap();
+        let packet = packet(raw);
+        (filter.matches(&packet), filter.matches_with(&packet, &Context))
+    };
+    assert_eq!(matches("m/50", FAR), (false, true));
+    assert_eq!(matches("m/50", POSITION), (false, false));
+    assert_eq!(matches("f/N1CALL-9/10", OBJECT), (false, true));
+    assert_eq!(matches("f/N2CALL/10", OBJECT), (false, false));
+    assert_eq!(matches("t/o/n1call-9/10", OBJECT), (false, true));
+    assert_eq!(matches("t/p/N1CALL-9/10", OBJECT), (false, false));
+    assert_eq!(matches("q//I", POSITION), (false, true));
+    assert_eq!(matches("q//I", FAR), (false, false));
+    assert_eq!(matches("q/C/I", MESSAGE), (true, true));
+}
+
+#[test]
+fn invalid_filters() {
+    let invalid = [
+        "r/49/-72", "r/49/-72/x", "r/49/-72/inf", "m/", "f/N0CALL", "f//10", "a/1/2/3", "p", "p/", "b/N0CALL/",
+        "t/x", "t/", "t/p/N0CALL", "t/p//10", "s", "s//", "q", "q/C/X", "x/1", "r/1/2/3 bogus",
+    ];
+    for filter in &invalid {
+        assert!(Filter::parse(filter).is_err(), "{} accepted", filter);
+    }
+    let filter: Filter = "  r/49/-72/50   -p/N0 ".parse().unwrap();
+    assert_eq!(filter.to_string(), "r/49/-72/50 -p/N0");
+}
@@ -0,0 +1,44 @@
+extern crate fap;
+
+const TNC2: &[u8] = b"OH2RDP-1>BEACON-15,OH2RDG*,WIDE:!6028.51N/02505.68E#PHG7220/RELAY,WIDE, OH2AP Jarvenpaa";
+
+#[test]
+fn kiss_round_trip() {
+    let kiss = fap::tnc2_to_kiss(TNC2, 0).unwrap();
+    assert_eq!(kiss.first(), Some(&0xc0));
+    assert_eq!(kiss.last(), Some(&0xc0));
+    let (tnc2, tnc_id) = fap::kiss_to_tnc2(&kiss).unwrap();
+    assert_eq!(tnc2, TNC2);
+    assert_eq!(tnc_id, 0);
+
+    let (_, tnc_id) = fap::kiss_to_tnc2(&fap::tnc2_to_kiss(TNC2, 0x10).unwrap()).unwrap();
+    assert_eq!(tnc_id, 0x10);
+}
+
+#[test]
+fn ax25_round_trip() {
+    let ax25 = fap::tnc2_to_ax25(TNC2).unwrap();
+    assert_eq!(&ax25[..7], &[b'B' << 1, b'E' << 1, b'A' << 1, b'C' << 1, b'O' << 1, b'N' << 1, 0xe0 | 15 << 1]);
+    assert_eq!(fap::ax25_to_tnc2(&ax25).unwrap(), TNC2);
+}
+
+#[test]
+fn conversion_errors() {
+    match fap::tnc2_to_ax25(b"no header here") {
+        Err(fap::Error::Other(msg)) => assert_eq!(msg, "Failed to separate header and body of TNC-2 packet."),
+        other => panic!("unexpected result {:?}", other),
+    }
+    match fap::tnc2_to_kiss(b"no header here", 0) {
+        Err(fap::Error::Other(msg)) => assert_eq!(msg, "Failed to separate header and body of TNC-2 packet."),
+        other => panic!("unexpected result {:?}", other),
+    }
+    match fap::ax25_to_tnc2(b"short") {
+        Err(fap::Error::Other(msg)) => assert_eq!(msg, "Too short AX.25 frame (5 bytes)."),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(fap::kiss_to_tnc2(&[]).is_err());
+    assert!(fap::kiss_to_tnc2(&[0u8; 600]).is_err());
+
+    // APRS-IS only packets can not go to RF
+    assert!(fap::tnc2_to_ax25(b"N0CALL>APRS,qAR,igate:>Status").is_err());
+}
@@ -0,0 +1,142 @@
+extern crate fap;
+
+use fap::igate::Igate;
+use fap::{tnc2_to_ax25, tnc2_to_kiss, PacketBuilder, Query, QueryResponder};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(1_500_000_000 + secs)
+}
+
+fn igate() -> Igate {
+    Igate::new(PacketBuilder::new("N0CALL-10").destination("APZ001").path(&["WIDE1-1"]))
+}
+
+#[test]
+fn rf_to_is() {
+    let mut igate = igate();
+    let gated = [
+        ("N1CALL>APRS,WIDE1-1*,WIDE2-1:>Status", "N1CALL>APRS,WIDE1-1*,WIDE2-1,qAR,N0CALL-10:>Status"),
+        ("N2CALL-9>T2SP0W,WIDE1-1:`(_fn\"Oj/\r\n", "N2CALL-9>T2SP0W,WIDE1-1,qAR,N0CALL-10:`(_fn\"Oj/"),
+        ("N3CALL>APRS::N1CALL   :Hello{1", "N3CALL>APRS,qAR,N0CALL-10::N1CALL   :Hello{1"),
+        // third-party packets from another RF network
+        ("N4CALL>APRS,WIDE1-1*:}N5CALL>APRS,GATE,WIDE2*:>Far away", "N5CALL>APRS,GATE,WIDE2*,qAR,N0CALL-10:>Far away"),
+    ];
+    for &(rf, is) in &gated {
+        assert_eq!(igate.rf_to_is(rf, at(0)).as_deref(), Some(is), "{}", rf);
+    }
+
+    let dropped = [
+        "N6CALL>APRS,NOGATE:>Status",
+        "N6CALL>APRS,RFONLY,WIDE1-1:>Status",
+        "N6CALL>APRS,TCPIP*:>Status",
+        "N6CALL>APRS,TCPXX*,WIDE1-1*:>Status",
+        "N6CALL>APRS,WIDE1-1*,qAR,N7CALL:>Status",
+        // our own third-party packets, digipeated back to us
+        "N0CALL-10>APZ001,DIGI*:}N3CALL>APRS,TCPIP,N0CALL-10*::N1CALL   :Hi",
+        "N6CALL>APRS:?APRS?",
+        "N6CALL>APRS:}N7CALL>APRS:?IGATE?",
+        "not a packet",
+    ];
+    for rf in &dropped {
+        assert_eq!(igate.rf_to_is(rf, at(0)), None, "{}", rf);
+    }
+}
+
+#[test]
+fn duplicates() {
+    let mut igate = igate();
+    let packet = "N1CALL>APRS,WIDE1-1*:!4903.50N/07201.75W-";
+    assert!(igate.rf_to_is(packet, at(0)).is_some());
+    assert!(igate.rf_to_is("N1CALL>APRS,DIGI1*,WIDE1*:!4903.50N/07201.75W-", at(10)).is_none());
+    assert!(igate.rf_to_is(packet, at(30)).is_none());
+    assert!(igate.rf_to_is(packet, at(31)).is_some());
+    // other content, or another destination, is not a duplicate
+    assert!(igate.rf_to_is("N1CALL>APRS,WIDE1-1*:!4903.50N/07201.75W>", at(32)).is_some());
+    assert!(igate.rf_to_is("N1CALL>APZ002,WIDE1-1*:!4903.50N/07201.75W>", at(32)).is_some());
+
+    let mut igate = igate.dupe_window(Duration::from_secs(5));
+    assert!(igate.rf_to_is(packet, at(100)).is_some());
+    assert!(igate.rf_to_is(packet, at(106)).is_some());
+}
+
+#[test]
+fn frames() {
+    let mut igate = igate().verified(false);
+    let tnc2 = "N1CALL>APRS,WIDE1-1*:>Status";
+    let kiss = tnc2_to_kiss(tnc2.as_bytes(), 0).unwrap();
+    assert_eq!(igate.kiss_to_is(&kiss, at(0)).unwrap().as_deref(), Some("N1CALL>APRS,WIDE1-1*,qAO,N0CALL-10:>Status"));
+    let ax25 = tnc2_to_ax25(b"N2CALL>APRS,WIDE1-1*:>Status").unwrap();
+    assert_eq!(igate.ax25_to_is(&ax25, at(0)).unwrap().as_deref(), Some("N2CALL>APRS,WIDE1-1*,qAO,N0CALL-10:>Status"));
+    assert!(igate.kiss_to_is(b"\xc0\x00\xc0", at(0)).is_err());
+}
+
+#[test]
+fn is_to_rf() {
+    let mut igate = igate();
+    igate.rf_to_is("N1CALL-7>APRS,WIDE1-1*:!4903.50N/07201.75W>", at(0));
+    igate.rf_to_is("N2CALL>APRS:>Local", at(0));
+    assert!(igate.is_local("n1call-7", at(60)));
+    assert!(!igate.is_local("N3CALL", at(60)));
+
+    let message = "N3CALL>APRS,TCPIP*,qAC,T2TEST::N1CALL-7 :Hello{1";
+    assert_eq!(igate.is_to_rf(message, at(60)).as_deref(),
+               Some("N0CALL-10>APZ001,WIDE1-1:}N3CALL>APRS,TCPIP,N0CALL-10*::N1CALL-7 :Hello{1"));
+    // the same message again from another server
+    assert_eq!(igate.is_to_rf("N3CALL>APRS,TCPIP*,qAC,T2OTHER::N1CALL-7 :Hello{1", at(70)), None);
+    assert_eq!(igate.messages_gated(), 1);
+
+    // the sender's next position follows, once
+    let position = "N3CALL>APRS,TCPIP*,qAC,T2TEST:!4903.50N/07201.75W-";
+    assert_eq!(igate.is_to_rf(position, at(80)).as_deref(),
+               Some("N0CALL-10>APZ001,WIDE1-1:}N3CALL>APRS,TCPIP,N0CALL-10*:!4903.50N/07201.75W-"));
+    assert_eq!(igate.is_to_rf("N3CALL>APRS,TCPIP*,qAC,T2TEST:!4903.51N/07201.75W-", at(90)), None);
+
+    let stays = [
+        // addressee not heard on RF, or not for long
+        "N3CALL>APRS,TCPIP*,qAC,T2TEST::N9CALL   :Hello{2",
+        // sender is local, the addressee hears it directly
+        "N2CALL>APRS,TCPIP*,qAC,T2TEST::N1CALL-7 :Hello{3",
+        "N3CALL>APRS,TCPXX*,qAX,T2TEST::N1CALL-7 :Hello{4",
+        "N3CALL>APRS,TCPIP*,qAX,T2TEST::N1CALL-7 :Hello{5",
+        "N3CALL>APRS,TCPIP*,NOGATE,qAC,T2TEST::N1CALL-7 :Hello{6",
+        "N3CALL>APRS,TCPIP*,qAC,T2TEST:>Status",
+        "N4CALL>APRS,TCPIP*,qAC,T2TEST:!4903.50N/07201.75W-",
+        "N0CALL-10>APRS,TCPIP*,qAC,T2TEST::N1CALL-7 :Own message{7",
+    ];
+    for is in &stays {
+        assert_eq!(igate.is_to_rf(is, at(100)), None, "{}", is);
+    }
+    assert!(igate.is_to_rf("N3CALL>APRS,TCPIP*,qAC,T2TEST::N1CALL-7 :Late{8", at(60 + 30 * 60)).is_none());
+
+    let mut receive_only = self::igate().verified(false);
+    receive_only.rf_to_is("N1CALL-7>APRS,WIDE1-1*:>Local", at(0));
+    assert_eq!(receive_only.is_to_rf(message, at(1)), None);
+}
+
+#[test]
+fn igate_query() {
+    let mut igate = igate();
+    let builder = PacketBuilder::new("N0CALL-10");
+    for call in &["N1CALL", "N2CALL", "N3CALL"] {
+        igate.rf_to_is(&format!("{}>APRS:>Local", call), at(0));
+    }
+    igate.is_to_rf("N4CALL>APRS,TCPIP*,qAC,T2TEST::N1CALL   :Hi", at(1));
+    assert_eq!(igate.local_stations(at(1)), 3);
+    assert_eq!(igate.local_stations(at(3600)), 0);
+    let query = Query::parse("N4CALL>APRS:?IGATE?").unwrap();
+    assert_eq!(igate.respond(&query, &builder).unwrap(), ["N0CALL-10>APRS:<IGATE,MSG_CNT=1,LOC_CNT=3"]);
+
+    // stations are counted at the time of the query
+    let raw = "N4CALL>APRS,TCPIP*,qAC,T2TEST:?IGATE?";
+    assert_eq!(igate.is_to_rf(raw, at(3600)), None);
+    let query = Query::parse(raw).unwrap();
+    assert_eq!(igate.respond(&query, &builder).unwrap(), ["N0CALL-10>APRS:<IGATE,MSG_CNT=1,LOC_CNT=0"]);
+    igate.rf_to_is("N1CALL>APRS:>Back", at(3600));
+    assert_eq!(igate.respond(&query, &builder).unwrap(), ["N0CALL-10>APRS:<IGATE,MSG_CNT=1,LOC_CNT=1"]);
+    // packets heard out of order do not count stations no longer local
+    igate.rf_to_is("N2CALL>APRS:>Late", at(1000));
+    assert_eq!(igate.respond(&query, &builder).unwrap(), ["N0CALL-10>APRS:<IGATE,MSG_CNT=1,LOC_CNT=1"]);
+    assert_eq!(igate.is_to_rf(raw, at(7200)), None);
+    assert_eq!(igate.respond(&query, &builder).unwrap(), ["N0CALL-10>APRS:<IGATE,MSG_CNT=1,LOC_CNT=0"]);
+}
@@ -0,0 +1,144 @@
+extern crate fap;
+
+use fap::{Bulletin, Error, Message, MessageIds, OwnedPacket, PacketBuilder, PacketType, MAX_MESSAGE_LEN};
+
+fn parse(raw: &str) -> OwnedPacket {
+    let packet = OwnedPacket::new(raw).unwrap_or_else(|e| panic!("{}: {}", raw, e));
+    assert_eq!(packet.packet_type, Some(PacketType::Message), "{}", raw);
+    packet
+}
+
+#[test]
+fn messages() {
+    let builder = PacketBuilder::new("N1CALL").path(&["WIDE2-1"]);
+    let raw = builder.message(&Message::new("N0CALL", "Hello there"), None).unwrap();
+    assert_eq!(raw, ["N1CALL>APRS,WIDE2-1::N0CALL   :Hello there"]);
+    let packet = parse(&raw[0]);
+    assert_eq!(packet.destination.as_deref(), Some("N0CALL"));
+    assert_eq!(packet.message.as_deref(), Some("Hello there"));
+    assert_eq!(packet.message_id, None);
+
+    let mut ids = MessageIds::new();
+    let raw = builder.message(&Message::new("N0CALL-15", "Hi"), Some(&mut ids)).unwrap();
+    let packet = parse(&raw[0]);
+    assert_eq!(packet.destination.as_deref(), Some("N0CALL-15"));
+    assert_eq!(packet.message.as_deref(), Some("Hi"));
+    assert_eq!(packet.message_id.as_deref(), Some("01"));
+    let raw = builder.message(&Message::new("N0CALL-15", "Again"), Some(&mut ids)).unwrap();
+    assert_eq!(parse(&raw[0]).message_id.as_deref(), Some("02"));
+}
+
+#[test]
+fn splitting() {
+    let text = "The quick brown fox jumps over the lazy dog and keeps running until it reaches \
+                the river, where it finally stops to drink some water.";
+    let mut ids = MessageIds::new();
+    let raw = PacketBuilder::new("N1CALL").message(&Message::new("N0CALL", text), Some(&mut ids)).unwrap();
+    assert_eq!(raw.len(), 2);
+    let packets: Vec<OwnedPacket> = raw.iter().map(|raw| parse(raw)).collect();
+    assert_eq!(packets[0].message.as_deref(),
+               Some("The quick brown fox jumps over the lazy dog and keeps running until"));
+    assert_eq!(packets[0].message_id.as_deref(), Some("01"));
+    assert_eq!(packets[1].message_id.as_deref(), Some("02"));
+    let joined: Vec<&str> = packets.iter().map(|p| p.message.as_deref().unwrap()).collect();
+    assert_eq!(joined.join(" "), text);
+
+    let long_word = "x".repeat(150);
+    let raw = PacketBuilder::new("N1CALL").message(&Message::new("N0CALL", &long_word), None).unwrap();
+    let lengths: Vec<usize> = raw.iter().map(|raw| parse(raw).message.unwrap().len()).collect();
+    assert_eq!(lengths, [MAX_MESSAGE_LEN, MAX_MESSAGE_LEN, 16]);
+
+    let unicode = "ä".repeat(MAX_MESSAGE_LEN);
+    let raw = PacketBuilder::new("N1CALL").message(&Message::new("N0CALL", &unicode), None).unwrap();
+    assert_eq!(raw.len(), 1);
+}
+
+#[test]
+fn reply_acks() {
+    let mut ids = MessageIds::new();
+    let builder = PacketBuilder::new("N1CALL");
+    let raw = builder.message(&Message::new("N0CALL", "Hello").reply_ack(""), Some(&mut ids)).unwrap();
+    assert_eq!(raw, ["N1CALL>APRS::N0CALL   :Hello{01}"]);
+    let raw = builder.message(&Message::new("N0CALL", "Yes").reply_ack("7Q"), Some(&mut ids)).unwrap();
+    assert_eq!(raw, ["N1CALL>APRS::N0CALL   :Yes{02}7Q"]);
+    // libfap, like Ham::APRS::FAP, leaves reply-ack IDs in the message text
+    let packet = parse(&raw[0]);
+    assert_eq!(packet.message.as_deref(), Some("Yes{02}7Q"));
+    assert_eq!(packet.message_id, None);
+
+    assert!(builder.message(&Message::new("N0CALL", "Hello").reply_ack("AB"), None).is_err());
+    assert!(builder.message(&Message::new("N0CALL", "Hello").reply_ack("A}B"), Some(&mut ids)).is_err());
+}
+
+#[test]
+fn message_ids() {
+    let mut ids = MessageIds::new();
+    assert_eq!(ids.next_id(), "01");
+    let mut ids = MessageIds::after("0Z").unwrap();
+    assert_eq!(ids.next_id(), "10");
+    let mut ids = MessageIds::after("zy").unwrap();
+    assert_eq!(ids.next_id(), "ZZ");
+    assert_eq!(ids.next_id(), "01");
+    assert_eq!(MessageIds::after("123"), None);
+    assert_eq!(MessageIds::after("+1"), None);
+}
+
+#[test]
+fn acks_and_rejs() {
+    let builder = PacketBuilder::new("N1CALL");
+    let raw = builder.ack("N0CALL-9", "42").unwrap();
+    assert_eq!(raw, "N1CALL>APRS::N0CALL-9 :ack42");
+    let packet = parse(&raw);
+    assert_eq!(packet.destination.as_deref(), Some("N0CALL-9"));
+    assert_eq!(packet.message_ack.as_deref(), Some("42"));
+
+    let raw = builder.rej("N0CALL-9", "AbC12").unwrap();
+    assert_eq!(parse(&raw).message_nack.as_deref(), Some("AbC12"));
+
+    assert!(builder.ack("N0CALL-9", "123456").is_err());
+    assert!(builder.ack("N0CALL-9", "").is_err());
+    assert!(builder.rej("N0CALL-9", "1 2").is_err());
+}
+
+#[test]
+fn bulletins() {
+    let builder = PacketBuilder::new("N1CALL");
+    let cases = [
+        (Bulletin::General(0), "BLN0"),
+        (Bulletin::Group(3, "WX".to_string()), "BLN3WX"),
+        (Bulletin::Announcement('Q'), "BLNQ"),
+        (Bulletin::Nws("WARN".to_string()), "NWS-WARN"),
+    ];
+    for &(ref bulletin, addressee) in &cases {
+        let raw = builder.bulletin(bulletin, "Net tonight at 8pm").unwrap();
+        let packet = parse(&raw);
+        assert_eq!(packet.destination.as_deref(), Some(addressee));
+        assert_eq!(packet.message.as_deref(), Some("Net tonight at 8pm"));
+    }
+    assert_eq!(builder.bulletin(&Bulletin::General(1), "Hi").unwrap(), "N1CALL>APRS::BLN1     :Hi");
+
+    for bulletin in &[Bulletin::General(10), Bulletin::Group(1, "TOOLONG".to_string()),
+                      Bulletin::Group(1, "wx".to_string()), Bulletin::Announcement('1'),
+                      Bulletin::Nws(String::new())] {
+        assert!(builder.bulletin(bulletin, "Hi").is_err(), "{:?}", bulletin);
+    }
+    assert!(builder.bulletin(&Bulletin::General(1), &"x".repeat(MAX_MESSAGE_LEN + 1)).is_err());
+}
+
+#[test]
+fn invalid_messages() {
+    let builder = PacketBuilder::new("N1CALL");
+    let rejected = |message: Message| match builder.message(&message, None) {
+        Err(Error::Encode(_)) => {},
+        other => panic!("{:?} not rejected: {:?}", message, other),
+    };
+    rejected(Message::new("N0CALL", ""));
+    rejected(Message::new("N0CALL", "  "));
+    rejected(Message::new("N0CALL", "a{b"));
+    rejected(Message::new("N0CALL", "a|b"));
+    rejected(Message::new("N0CALL", "a~b"));
+    rejected(Message::new("N0CALL", "a\tb"));
+    rejected(Message::new("", "Hi"));
+    rejected(Message::new("TOOLONGCALL", "Hi"));
+    rejected(Message::new("N0 CALL", "Hi"));
+}
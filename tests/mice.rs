@@ -0,0 +1,143 @@
+#[macro_use] extern crate approx;
+extern crate fap;
+
+use fap::{Error, MicEMessage, MicEReport, OwnedPacket, PacketBuilder, PacketType, PositionFormat};
+
+fn round_trip(report: &MicEReport) -> OwnedPacket {
+    let raw = PacketBuilder::new("N0CALL").path(&["WIDE1-1"]).mice(report).unwrap();
+    let packet = OwnedPacket::new(raw.as_str()).unwrap_or_else(|e| panic!("{}: {}", raw, e));
+    assert_eq!(packet.packet_type, Some(PacketType::Location), "{}", raw);
+    assert_eq!(packet.format, Some(PositionFormat::MicE), "{}", raw);
+    packet
+}
+
+#[test]
+fn position_course_speed() {
+    let report = MicEReport::new(-32.852, 151.684)
+        .symbol('/', '>')
+        .message(MicEMessage::InService)
+        .course_speed(305, 74.08);
+    assert_eq!(PacketBuilder::new("VK2YCJ-9").mice(&report).unwrap(), "VK2YCJ-9>S2U1Q2:`OE p#!>/");
+
+    let positions = [(49.0583, -72.0292), (0.0, 0.0), (-0.5, 5.25), (89.9999, -179.9999), (-45.1, 105.7), (12.3, 99.99)];
+    for &(latitude, longitude) in &positions {
+        for &(course, speed) in &[(0, 0.0), (1, 1.852), (90, 185.2), (360, 370.4), (199, 1479.748)] {
+            let packet = round_trip(&MicEReport::new(latitude, longitude).course_speed(course, speed));
+            assert_abs_diff_eq!(packet.latitude.unwrap(), latitude, epsilon = 0.0001);
+            assert_abs_diff_eq!(packet.longitude.unwrap(), longitude, epsilon = 0.0001);
+            assert_eq!(packet.course, Some(course));
+            assert_abs_diff_eq!(packet.speed.unwrap(), speed, epsilon = 0.001);
+        }
+    }
+}
+
+#[test]
+fn messages() {
+    let messages = [MicEMessage::OffDuty, MicEMessage::EnRoute, MicEMessage::InService, MicEMessage::Returning,
+                    MicEMessage::Committed, MicEMessage::Special, MicEMessage::Priority, MicEMessage::Emergency,
+                    MicEMessage::Custom(0), MicEMessage::Custom(6)];
+    for &message in &messages {
+        let packet = round_trip(&MicEReport::new(40.7755, -119.1967).message(message));
+        let bits = packet.messagebits.unwrap();
+        assert_eq!(MicEMessage::from_bits(&bits), Some(message), "{}", bits);
+    }
+    assert_eq!(MicEMessage::from_bits("110"), Some(MicEMessage::EnRoute));
+    assert_eq!(MicEMessage::from_bits("022"), Some(MicEMessage::Custom(4)));
+    assert_eq!(MicEMessage::from_bits("120"), None);
+}
+
+#[test]
+fn altitude_telemetry_comment() {
+    let report = MicEReport::new(40.9228, -117.8228)
+        .symbol('\\', 'u')
+        .course_speed(246, 122.232)
+        .altitude(1303.0)
+        .comment("Hello")
+        .telemetry(12, &[1, 200, 3000, 8280, 0], Some(0b1000_0001));
+    let packet = round_trip(&report);
+    assert_eq!(packet.symbol_table, Some('\\'));
+    assert_eq!(packet.symbol_code, Some('u'));
+    assert_eq!(packet.altitude, Some(1303.0));
+    assert_eq!(packet.comment.as_deref(), Some("Hello"));
+    let telemetry = packet.telemetry.unwrap();
+    assert_eq!(telemetry.seq, Some(12));
+    assert_eq!(telemetry.values, [Some(1.0), Some(200.0), Some(3000.0), Some(8280.0), Some(0.0)]);
+    assert_eq!(telemetry.bits.as_deref(), Some("10000001"));
+
+    let packet = round_trip(&MicEReport::new(10.0, 10.0).altitude(-100.0).telemetry(1, &[42], None));
+    assert_eq!(packet.altitude, Some(-100.0));
+    let telemetry = packet.telemetry.unwrap();
+    assert_eq!(telemetry.values[0], Some(42.0));
+    assert_eq!(telemetry.values[1], None);
+}
+
+#[test]
+fn ambiguity() {
+    for ambiguity in 1..5 {
+        let packet = round_trip(&MicEReport::new(49.0583, -72.0292).ambiguity(ambiguity));
+        assert_eq!(packet.pos_ambiguity, Some(u32::from(ambiguity)));
+        let digits = 10f64.powi(i32::from(ambiguity) - 2);
+        assert_abs_diff_eq!(packet.latitude.unwrap(), 49.0583, epsilon = digits / 60.0);
+    }
+    let raw = PacketBuilder::new("N0CALL").mice(&MicEReport::new(49.0583, -72.0292).ambiguity(2)).unwrap();
+    assert!(raw.starts_with("N0CALL>TYPSLZ:"), "{}", raw);
+}
+
+// Decoding a real packet, encoding what came out and decoding again gives the same values
+#[test]
+fn reencode() {
+    let packets: &[&[u8]] = &[
+        b"VK2YCJ-9>S2U1Q2,VK2RTZ-1*,WIDE2-2,qAR,VK2ZEN-5:\x60OE p#!>/",
+        b"KK6IOS-9>TP5USW,qAR,W7TKO-1:\x60-MAr^Ju\x5c\x60\x22B4}_%",
+        b"W8KHU-9>S9SVUV,SNOW*,qAR,KJ6NKR-2:\x60/Exo\x22qj/\x60\x22Bi}_%",
+        b"N7OCC-9>S9TRVX,VIRGPK*,WIDE1*,WIDE2-1,qAR,KG7AIQ-1:\x27.J,\x22Slk/]\x22@p}",
+        b"KG7BUN-2>TR1RTT,KE7QP,WIDE1,KFALLS,WIDE2*,qAR,ASHLND:\x6005\x1fl \x1ck/\x60\x22Cj}_\x22",
+    ];
+    for raw in packets {
+        let original = OwnedPacket::new(*raw).unwrap();
+        let mut report = MicEReport::new(original.latitude.unwrap(), original.longitude.unwrap())
+            .symbol(original.symbol_table.unwrap(), original.symbol_code.unwrap())
+            .message(MicEMessage::from_bits(original.messagebits.as_ref().unwrap()).unwrap())
+            .course_speed(original.course.unwrap_or(0), original.speed.unwrap_or(0.0));
+        if let Some(altitude) = original.altitude {
+            report = report.altitude(altitude);
+        }
+        if let Some(ref comment) = original.comment {
+            report = report.comment(comment);
+        }
+        let raw = PacketBuilder::new(&original.src_callsign).mice(&report).unwrap();
+        let packet = OwnedPacket::new(raw.as_str()).unwrap();
+        assert_eq!(packet.dst_callsign, original.dst_callsign, "{}", raw);
+        assert_eq!(packet.latitude, original.latitude, "{}", raw);
+        assert_eq!(packet.longitude, original.longitude, "{}", raw);
+        assert_eq!(packet.symbol_table, original.symbol_table, "{}", raw);
+        assert_eq!(packet.symbol_code, original.symbol_code, "{}", raw);
+        assert_eq!(packet.messagebits, original.messagebits, "{}", raw);
+        assert_eq!(packet.course.unwrap_or(0), original.course.unwrap_or(0), "{}", raw);
+        assert_abs_diff_eq!(packet.speed.unwrap_or(0.0), original.speed.unwrap_or(0.0), epsilon = 0.001);
+        assert_eq!(packet.altitude, original.altitude, "{}", raw);
+        assert_eq!(packet.comment, original.comment, "{}", raw);
+    }
+}
+
+#[test]
+fn invalid_reports() {
+    let builder = PacketBuilder::new("N0CALL");
+    let base = MicEReport::new(49.0583, -72.0292);
+    let rejected = |report: MicEReport| match builder.mice(&report) {
+        Err(Error::Encode(_)) => {},
+        other => panic!("{:?} not rejected: {:?}", report, other),
+    };
+    rejected(MicEReport::new(-90.5, 0.0));
+    rejected(MicEReport::new(0.0, f64::NAN));
+    rejected(base.clone().symbol('/', ' '));
+    rejected(base.clone().message(MicEMessage::Custom(7)));
+    rejected(base.clone().ambiguity(5));
+    rejected(base.clone().course_speed(361, 0.0));
+    rejected(base.clone().course_speed(0, 800.0 * 1.852));
+    rejected(base.clone().altitude(-10001.0));
+    rejected(base.clone().telemetry(0, &[], None));
+    rejected(base.clone().telemetry(0, &[1, 2], Some(1)));
+    rejected(base.clone().telemetry(8281, &[1], None));
+    rejected(base.clone().comment("a\rb"));
+}
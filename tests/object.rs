@@ -0,0 +1,89 @@
+#[macro_use] extern crate approx;
+extern crate fap;
+
+use fap::{Error, ItemReport, ObjectReport, OwnedPacket, PacketBuilder, PacketType, Parser, PositionFormat, PositionReport,
+          Timestamp};
+use std::time::{Duration, UNIX_EPOCH};
+
+const TIME: u64 = 1528597526;
+
+fn parse(raw: &str) -> OwnedPacket {
+    let parser = Parser::builder().reference_time(UNIX_EPOCH + Duration::from_secs(TIME + 60)).build();
+    parser.parse(raw).unwrap_or_else(|e| panic!("{}: {}", raw, e)).to_owned()
+}
+
+#[test]
+fn objects() {
+    let time = Timestamp::Hms(UNIX_EPOCH + Duration::from_secs(TIME));
+    let position = PositionReport::new(49.0583, -72.0292)
+        .symbol('/', '>')
+        .course_speed(88, 66.7)
+        .comment("Lead car");
+    let object = ObjectReport::new("LEAD CAR", time, position);
+    let raw = PacketBuilder::new("N0CALL").path(&["WIDE2-2"]).object(&object).unwrap();
+    assert_eq!(raw, "N0CALL>APRS,WIDE2-2:;LEAD CAR *022526h4903.50N/07201.75W>088/036Lead car");
+    let packet = parse(&raw);
+    assert_eq!(packet.packet_type, Some(PacketType::Object));
+    assert_eq!(packet.object_name.as_deref(), Some("LEAD CAR "));
+    assert_eq!(packet.alive, Some(true));
+    assert_eq!(packet.timestamp, Some(TIME));
+    assert_abs_diff_eq!(packet.latitude.unwrap(), 49.0583, epsilon = 0.0001);
+    assert_abs_diff_eq!(packet.longitude.unwrap(), -72.0292, epsilon = 0.0001);
+    assert_eq!(packet.symbol_code, Some('>'));
+    assert_eq!(packet.course, Some(88));
+    assert_eq!(packet.comment.as_deref(), Some("Lead car"));
+
+    let packet = parse(&PacketBuilder::new("N0CALL").object(&object.clone().killed()).unwrap());
+    assert_eq!(packet.alive, Some(false));
+    assert_eq!(packet.object_name.as_deref(), Some("LEAD CAR "));
+
+    let compressed = ObjectReport::new("123456789", time, PositionReport::new(-33.5, 151.25).compressed(true));
+    let packet = parse(&PacketBuilder::new("N0CALL").object(&compressed).unwrap());
+    assert_eq!(packet.format, Some(PositionFormat::Compressed));
+    assert_eq!(packet.object_name.as_deref(), Some("123456789"));
+    assert_abs_diff_eq!(packet.latitude.unwrap(), -33.5, epsilon = 0.0001);
+}
+
+#[test]
+fn items() {
+    let item = ItemReport::new("AID#2", PositionReport::new(49.0583, -72.0292).symbol('\\', 'm').comment("Water"));
+    let raw = PacketBuilder::new("N0CALL").item(&item).unwrap();
+    assert_eq!(raw, "N0CALL>APRS:)AID#2!4903.50N\\07201.75WmWater");
+    let packet = parse(&raw);
+    assert_eq!(packet.packet_type, Some(PacketType::Item));
+    assert_eq!(packet.item_name.as_deref(), Some("AID#2"));
+    assert_eq!(packet.alive, Some(true));
+    assert_eq!(packet.symbol_table, Some('\\'));
+    assert_eq!(packet.comment.as_deref(), Some("Water"));
+
+    let packet = parse(&PacketBuilder::new("N0CALL").item(&item.killed()).unwrap());
+    assert_eq!(packet.alive, Some(false));
+    assert_eq!(packet.item_name.as_deref(), Some("AID#2"));
+
+    let item = ItemReport::new("ABCDEFGHI", PositionReport::new(1.0, 2.0).compressed(true).altitude(100.0));
+    let packet = parse(&PacketBuilder::new("N0CALL").item(&item).unwrap());
+    assert_eq!(packet.item_name.as_deref(), Some("ABCDEFGHI"));
+    assert_abs_diff_eq!(packet.altitude.unwrap(), 100.0, epsilon = 0.2);
+}
+
+#[test]
+fn invalid_names() {
+    let builder = PacketBuilder::new("N0CALL");
+    let time = Timestamp::Dhm(UNIX_EPOCH + Duration::from_secs(TIME));
+    let position = PositionReport::new(49.0583, -72.0292);
+    for name in &["", "         ", "1234567890", "tab\there", "Aü"] {
+        match builder.object(&ObjectReport::new(name, time, position.clone())) {
+            Err(Error::Encode(_)) => {},
+            other => panic!("{:?} not rejected: {:?}", name, other),
+        }
+    }
+    for name in &["AB", "1234567890", "A!B", "A_B", "A\nB"] {
+        match builder.item(&ItemReport::new(name, position.clone())) {
+            Err(Error::Encode(_)) => {},
+            other => panic!("{:?} not rejected: {:?}", name, other),
+        }
+    }
+    assert!(builder.object(&ObjectReport::new("OBJ", time, position.clone().timestamp(time))).is_err());
+    assert!(builder.item(&ItemReport::new("ITEM", position.clone().timestamp(time))).is_err());
+    assert!(builder.item(&ItemReport::new("ITEM", position.clone().comment("a\rb"))).is_err());
+}
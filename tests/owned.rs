@@ -0,0 +1,88 @@
+extern crate aprs;
+extern crate fap;
+
+use aprs::{Packet as AprsPacket, Degrees};
+use fap::{OwnedPacket, PacketType, PositionFormat, Digipeater};
+use std::sync::mpsc;
+use std::thread;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn owned_packet_is_send_and_sync() {
+    assert_send_sync::<OwnedPacket>();
+}
+
+#[test]
+fn owned_packet_copies_fields() {
+    let raw = "DISCOF>APT314,RAZOR*,WIDE1*,WIDE2-1,qAS,GERLCH:/022526h4046.40N/11912.12W-347/001/KG6YJN";
+    let packet = fap::Packet::new(raw).unwrap();
+    let owned = packet.to_owned();
+    drop(packet);
+
+    assert_eq!(owned.packet_type, Some(PacketType::Location));
+    assert_eq!(owned.format, Some(PositionFormat::Uncompressed));
+    assert_eq!(owned.src_callsign, "DISCOF");
+    assert_eq!(owned.dst_callsign.as_deref(), Some("APT314"));
+    assert_eq!(owned.digipeaters[0], Digipeater{ call: "RAZOR".to_string(), digipeated: true });
+    assert_eq!(owned.digipeaters[2], Digipeater{ call: "WIDE2-1".to_string(), digipeated: false });
+    assert_eq!(owned.path(), "RAZOR*,WIDE1*,WIDE2-1,qAS,GERLCH");
+    assert_eq!(owned.symbol_table, Some('/'));
+    assert_eq!(owned.symbol_code, Some('-'));
+    assert_eq!(owned.course(), Some(Degrees(347.0)));
+    assert_eq!(owned.raw_timestamp.as_deref(), Some("022526"));
+    assert_eq!(owned.comment.as_deref(), Some("/KG6YJN"));
+    assert_eq!(owned.latitude(), fap::Packet::new(raw).unwrap().latitude());
+}
+
+#[test]
+fn owned_packet_clone_and_eq() {
+    let raw = "VK2YCJ-9>S2U1Q2,VK2RTZ-1*,WIDE2-2,qAR,VK2ZEN-5:\x60OE p#!>/";
+    let owned = OwnedPacket::new(raw).unwrap();
+    let copy = owned.clone();
+    assert_eq!(owned, copy);
+    assert_eq!(copy.format, Some(PositionFormat::MicE));
+    assert!(copy.messagebits.is_some());
+}
+
+#[test]
+fn owned_packet_through_channel() {
+    let (tx, rx) = mpsc::channel();
+    let raws = vec![
+        "W7TKO-1>APMI01,TCPIP*,qAS,W7TKO:@060057z4057.57N/11742.38W#W7TKO",
+        "CW7293>APRS,TCPXX*,qAX,CWOP-4:@080812z3718.28N/12158.90W_224/001g002t055r000p000P000h76b10174eWUHU216DAVISVP2.",
+    ];
+    let handle = thread::spawn(move || {
+        for raw in raws {
+            tx.send(OwnedPacket::new(raw).unwrap()).unwrap();
+        }
+    });
+    let received: Vec<OwnedPacket> = rx.iter().collect();
+    handle.join().unwrap();
+    assert_eq!(received.len(), 2);
+    let wx = received[1].wx_report.as_ref().unwrap();
+    assert_eq!(wx.wind_dir, Some(224));
+    assert_eq!(wx.humidity, Some(76));
+    assert!(received[1].wind_speed().is_some());
+}
+
+#[test]
+fn capabilities_and_status() {
+    let caps = OwnedPacket::new("N0CALL>APRS:<IGATE,MSG_CNT=10,LOC_CNT=3").unwrap();
+    assert_eq!(caps.packet_type, Some(PacketType::Capabilities));
+    assert_eq!(caps.capabilities.get("IGATE"), Some(&None));
+    assert_eq!(caps.capabilities.get("MSG_CNT"), Some(&Some("10".to_string())));
+
+    let status = OwnedPacket::new("N0CALL>APRS:>Net control tonight").unwrap();
+    assert_eq!(status.packet_type, Some(PacketType::Status));
+    assert_eq!(status.status.as_deref(), Some("Net control tonight"));
+}
+
+#[test]
+fn telemetry_bits() {
+    let tlm = OwnedPacket::new("N0CALL>APRS:T#005,199,000,255,073,123,01101001").unwrap();
+    let t = tlm.telemetry.unwrap();
+    assert_eq!(t.seq, Some(5));
+    assert_eq!(t.values[0], Some(199.0));
+    assert_eq!(t.bits.as_deref(), Some("01101001"));
+}
@@ -37,14 +37,15 @@ impl<'a> ParserTestCase<'a> {
    fn run(&self) {
       const EPSILON : f32 = 0.0001;
       let rawstr = String::from_utf8_lossy(self.raw).to_owned();
-      let parsed = fap::Packet::new(self.raw).unwrap();
-      let trimmed_comment = parsed.comment().map(|v| v.to_owned().trim().to_string());
+      let parser = fap::Parser::builder().comment_mode(fap::CommentMode::PerlCompatible).build();
+      let parsed = parser.parse(self.raw).unwrap();
+      let comment = parsed.comment();
       let altitude = self.altitude.map(|v| Feet::from(v).0).unwrap_or(0.0);
       let speed = self.speed.map(|v| Knots::from(v).0).unwrap_or(0.0);
       let course = self.course.map(|v| v.0).unwrap_or(0.0);
       assert_eq!( parsed.source(), self.src_callsign, "bad srccall in {:?}", rawstr);
       assert_eq!( parsed.destination(), self.dst_callsign.map(|v| Cow::Borrowed(v)), "bad dstcall in {:?}", rawstr);
-      assert_eq!( trimmed_comment.as_ref().map(|v| v.as_str()), self.comment, "bad comment in {:?}", rawstr);
+      assert_eq!( comment.as_ref().map(|v| v.as_ref()), self.comment, "bad comment in {:?}", rawstr);
       assert_abs_diff_eq!( parsed.latitude().unwrap_or(0.0), self.latitude.unwrap_or(0.0), epsilon=EPSILON);
       assert_abs_diff_eq!( parsed.longitude().unwrap_or(0.0), self.longitude.unwrap_or(0.0), epsilon=EPSILON);
       assert_abs_diff_eq!( parsed.speed().map(|v| v.0).unwrap_or(0.0), speed, epsilon=EPSILON);
@@ -61,6 +62,49 @@ fn malformed_message() {
 
 }
 
+#[test]
+fn capabilities_with_values() {
+    let packet = fap::Packet::new("N0CALL>APRS:<IGATE,MSG_CNT=10,LOC_CNT=3").unwrap();
+    let caps: Vec<(String, Option<String>)> = packet.capabilities().into_iter()
+        .map(|(key, value)| (key.into_owned(), value.map(|v| v.into_owned())))
+        .collect();
+    assert_eq!(caps, [("IGATE".to_string(), None),
+                      ("MSG_CNT".to_string(), Some("10".to_string())),
+                      ("LOC_CNT".to_string(), Some("3".to_string()))]);
+}
+
+#[test]
+fn telemetry_without_bits() {
+    let packet = fap::Packet::new("N0CALL>APRS:!4903.50N/07201.75W-|!!!!|").unwrap();
+    let telemetry = packet.telemetry().unwrap();
+    assert_eq!(telemetry.values[0], Some(0.0));
+    assert_eq!(telemetry.bits, None);
+}
+
+#[test]
+fn compressed_overlay() {
+    let packet = fap::OwnedPacket::new("N0CALL>APRS:!a5L!!<*e7#7P[").unwrap();
+    assert_eq!(packet.symbol_table, Some('0'));
+    assert_eq!(packet.symbol_code, Some('#'));
+    let packet = fap::OwnedPacket::new("N0CALL>APRS:!j5L!!<*e7#7P[").unwrap();
+    assert_eq!(packet.symbol_table, Some('9'));
+}
+
+#[test]
+fn compressed_altitude() {
+    // cs of 4610, 10004 feet
+    let packet = fap::OwnedPacket::new("N0CALL>APRS:!/5L!!<*e7OS]S").unwrap();
+    assert_abs_diff_eq!(packet.altitude.unwrap(), 10004.0 * 0.3048, epsilon=1.0);
+}
+
+#[test]
+fn weather_luminosity() {
+    let lumi = |raw: &str| fap::OwnedPacket::new(raw).unwrap().wx_report.unwrap().luminosity;
+    assert_eq!(lumi("N0CALL>APRS:_10090556c220s004g005t077r000p000P000h50b09900L123"), Some(123));
+    assert_eq!(lumi("N0CALL>APRS:_10090556c220s004g005t077r000p000P000h50b09900l123"), Some(1123));
+    assert_eq!(lumi("N0CALL>APRS:!4903.50N/07201.75W_220/004g005t077l023"), Some(1023));
+}
+
 // !!! CODE BELOW IS AUTOGENERATED, DO NOT EDIT !!!
 // Py: Some("KG6YJN!wv$!") != Pl: Some("KG6YJN")
 #[test]
@@ -243,7 +287,6 @@ fn parse_c822782a() {
 }
     
 #[test]
-#[ignore] // in ignored list
 fn parse_9bbe22d2() {
     ParserTestCase{ 
       raw: b"SNOW>APN383,qAR,KJ6IX-5:!3909.25N111952.99W#PHG2830/NVn,NCAn,TEMPn/WG6D/Snow Valley Peak, NV/A=009214",
@@ -574,7 +617,6 @@ fn parse_3ba3219c() {
 }
     
 #[test]
-#[ignore] // in ignored list
 fn parse_73dab7a9() {
     ParserTestCase{ 
       raw: b"HOUGH>APNKMP,KE6REA-3*,WIDE2-1,qAR,K6TTR-6:!4002.68NS12053.16W#PHG2134/NCAn/HOUGH/A=007240",
@@ -650,7 +692,6 @@ fn parse_b7b26e8c() {
 }
     
 #[test]
-#[ignore] // in ignored list
 fn parse_e57cfcb2() {
     ParserTestCase{ 
       raw: b"VIRGPK>APNU19,qAR,KE7UQK-3:!3945.37NS11927.68W#PHG7700/W3,NVn, WA6TLW, Virginia Pk A=008367",
@@ -776,7 +817,6 @@ fn parse_ff253b6d() {
 }
     
 #[test]
-#[ignore] // in ignored list
 fn parse_2caaa37c() {
     ParserTestCase{ 
       raw: b"VIRGPK>APNU19,qAS,GERLCH:!3945.37NS11927.68W#PHG7700/W3,NVn, WA6TLW, Virginia Pk A=008367",
@@ -878,7 +918,6 @@ fn parse_c4da2269() {
 }
     
 #[test]
-#[ignore] // in ignored list
 fn parse_e44a7b1a() {
     ParserTestCase{ 
       raw: b"KE6REA-3>APN382,qAR,K6TTR-6:!4020.22NS12052.23W#PHG5810/W2,NCAn/HAMILTON RIDGE/A=006850",
@@ -0,0 +1,92 @@
+extern crate aprs;
+extern crate fap;
+
+use aprs::Packet as AprsPacket;
+use fap::{CommentMode, Error, PacketType, Parser};
+use std::time::{Duration, UNIX_EPOCH};
+
+#[test]
+fn default_parser() {
+    let parser = Parser::new();
+    let packet = parser.parse("N0CALL>APRS,qAS,igate:!4903.50N/07201.75W-  Test  ").unwrap();
+    assert_eq!(packet.comment().unwrap(), "  Test  ");
+    assert!(packet.orig_packet().is_some());
+}
+
+#[test]
+fn ax25_mode() {
+    let raw = "N0CALL>APRS,qAS,igate:!4903.50N/07201.75W-Test";
+    assert!(Parser::builder().ax25(false).build().parse(raw).is_ok());
+    assert!(Parser::builder().ax25(true).build().parse(raw).is_err());
+    assert!(Parser::builder().ax25(true).build().parse("N0CALL>APRS,WIDE1-1:!4903.50N/07201.75W-Test").is_ok());
+}
+
+#[test]
+fn accepted_types() {
+    let parser = Parser::builder()
+        .accept(&[PacketType::Status])
+        .build();
+    assert!(parser.parse("N0CALL>APRS:>Status").is_ok());
+    match parser.parse("N0CALL>APRS:!4903.50N/07201.75W-Test") {
+        Err(Error::NotAccepted(Some(PacketType::Location))) => (),
+        other => panic!("unexpected result {:?}", other.map(|p| p.to_owned())),
+    }
+}
+
+#[test]
+fn keep_original() {
+    let raw = "N0CALL>APRS:>Status";
+    let parser = Parser::builder().keep_original(false).build();
+    let packet = parser.parse(raw).unwrap();
+    assert!(packet.orig_packet().is_none());
+    assert_eq!(packet.to_owned().orig_packet, None);
+
+    let parser = Parser::builder().keep_original(true).build();
+    assert_eq!(parser.parse(raw).unwrap().orig_packet().unwrap(), raw);
+}
+
+#[test]
+fn perl_compatible_comments() {
+    let parser = Parser::builder().comment_mode(CommentMode::PerlCompatible).build();
+    let packet = parser.parse("N0CALL>APRS:!4903.50N/07201.75W-  Test  ").unwrap();
+    assert_eq!(packet.comment().unwrap(), "Test");
+    let packet = parser.parse("N0CALL>APRS:!4903.50N/07201.75W-   ").unwrap();
+    assert_eq!(packet.comment(), None);
+    let packet = parser.parse("N0CALL>APRS:!4903.50N/07201.75W>088/036/Test/").unwrap();
+    assert_eq!(packet.comment().unwrap(), "Test/");
+    let packet = parser.parse("N0CALL>APRS:!4903.50N/07201.75W#PHG2360//Test").unwrap();
+    assert_eq!(packet.comment().unwrap(), "/Test");
+    let packet = parser.parse("BOB>APUX3:=/:MTJ03jES3. / ").unwrap();
+    assert_eq!(packet.comment(), None);
+    let packet = parser.parse("N0CALL>APRS:!4903.50N/07201.75W- \tTest\x7fing\r").unwrap();
+    assert_eq!(packet.comment().unwrap(), "Testing");
+    let packet = parser.parse("N0CALL>APRS:!4903.50N/07201.75W-\t\x01").unwrap();
+    assert_eq!(packet.comment(), None);
+
+    let raw = Parser::builder().comment_mode(CommentMode::Raw).build();
+    let packet = raw.parse("N0CALL>APRS:!4903.50N/07201.75W>088/036/Test ").unwrap();
+    assert_eq!(packet.comment().unwrap(), "/Test ");
+}
+
+#[test]
+fn reference_time() {
+    let parser = Parser::builder()
+        .reference_time(UNIX_EPOCH + Duration::from_secs(1528600000))
+        .build();
+    let hms = parser.parse("DISCOF>APT314,RAZOR*,WIDE1*,WIDE2-1,qAS,GERLCH:/022526h4046.40N/11912.12W-347/001/KG6YJN").unwrap();
+    assert_eq!(hms.timestamp(), Some(UNIX_EPOCH + Duration::from_secs(1528597526)));
+    let dhm = parser.parse("CW7293>APRS,TCPXX*,qAX,CWOP-4:@080812z3718.28N/12158.90W_224/001g002t055r000p000P000h76b10174eWUHU216DAVISVP2.").unwrap();
+    assert_eq!(dhm.timestamp(), Some(UNIX_EPOCH + Duration::from_secs(1528445520)));
+
+    // Without reference time the same packet resolves against the current time
+    let now = Parser::new().parse("CW7293>APRS,TCPXX*,qAX,CWOP-4:@080812z3718.28N/12158.90W_").unwrap();
+    assert!(now.timestamp() > dhm.timestamp());
+}
+
+#[test]
+fn cloned_parser_keeps_options() {
+    let parser = Parser::builder().accept(&[PacketType::Status]).build();
+    let clone = parser.clone();
+    drop(parser);
+    assert!(clone.parse("N0CALL>APRS:!4903.50N/07201.75W-Test").is_err());
+}
@@ -0,0 +1,76 @@
+extern crate fap;
+
+use fap::aprsis::{q_algorithm, Entry, QConstruct};
+use fap::Packet;
+
+#[test]
+fn q_constructs() {
+    let packets = [
+        ("DISCOF>APT314,RAZOR*,WIDE1*,WIDE2-1,qAS,GERLCH:>Hi", Some((QConstruct::Server, "GERLCH"))),
+        ("VK2YCJ-9>APRS,VK2RTZ-1*,WIDE2-2,qAR,VK2ZEN-5:>Hi", Some((QConstruct::Igate, "VK2ZEN-5"))),
+        ("CW7293>APRS,TCPXX*,qAX,CWOP-4:>Hi", Some((QConstruct::Unverified, "CWOP-4"))),
+        ("N0CALL>APRS,qAo,N1CALL:>Hi", Some((QConstruct::ClientOnly, "N1CALL"))),
+        ("N0CALL>APRS,qAI,T2A,T2B:>Hi", Some((QConstruct::Trace, "T2A"))),
+        ("N0CALL>APRS,WIDE1-1:>Hi", None),
+        ("N0CALL>APRS,qAR:>Hi", None),
+        ("N0CALL>APRS,qar,N1CALL:>Hi", None),
+    ];
+    for &(raw, expected) in &packets {
+        let packet = Packet::new(raw).unwrap();
+        let q = packet.q_construct();
+        assert_eq!(q.as_ref().map(|&(q, ref entry)| (q, entry.as_ref())), expected, "{}", raw);
+    }
+
+    let all = ["qAC", "qAX", "qAU", "qAo", "qAO", "qAS", "qAr", "qAR", "qAZ", "qAI"];
+    for name in &all {
+        let q = QConstruct::parse(name).unwrap();
+        assert_eq!(q.to_string(), *name);
+        assert_eq!(q.letter(), name.chars().nth(2).unwrap());
+    }
+    assert_eq!(QConstruct::parse("qAQ"), None);
+}
+
+#[test]
+fn igate() {
+    let verified = Entry::Rf { igate: "N0CALL-10", verified: true };
+    let receive_only = Entry::Rf { igate: "N0CALL-10", verified: false };
+    assert_eq!(q_algorithm("N1CALL>APRS:>Hi", verified).unwrap(), "N1CALL>APRS,qAR,N0CALL-10:>Hi");
+    assert_eq!(q_algorithm("N1CALL>APRS,WIDE2-1:>Hi", receive_only).unwrap(),
+               "N1CALL>APRS,WIDE2-1,qAO,N0CALL-10:>Hi");
+    // the body is left alone, colons and all
+    assert_eq!(q_algorithm("N1CALL>APRS::N2CALL   :Hi:there", verified).unwrap(),
+               "N1CALL>APRS,qAR,N0CALL-10::N2CALL   :Hi:there");
+
+    assert!(q_algorithm("N1CALL>APRS,TCPIP*:>Hi", verified).is_err());
+    assert!(q_algorithm("N1CALL>APRS,qAC,T2TEST:>Hi", verified).is_err());
+    assert!(q_algorithm("N1CALL>APRS,WIDE1-1,qAR,N0CALL-10:>Hi", verified).is_err());
+    assert!(q_algorithm("N1CALL>APRS", verified).is_err());
+    assert!(q_algorithm(">APRS:>Hi", verified).is_err());
+}
+
+#[test]
+fn server() {
+    let client = |login, verified| Entry::Client { server: "T2TEST", login, verified };
+    let cases = [
+        // the client's own packets
+        ("N1CALL>APRS,TCPIP*:>Hi", client("N1CALL", true), "N1CALL>APRS,TCPIP*,qAC,T2TEST:>Hi"),
+        ("N1CALL>APRS,TCPIP*:>Hi", client("N1CALL", false), "N1CALL>APRS,TCPIP*,qAX,T2TEST:>Hi"),
+        ("N1CALL>APRS,TCPIP*,qAR,N1CALL:>Hi", client("N1CALL", true), "N1CALL>APRS,TCPIP*,qAC,T2TEST:>Hi"),
+        // packets the client gated
+        ("N2CALL>APRS,WIDE1-1,qAR,N1CALL:>Hi", client("N1CALL", true), "N2CALL>APRS,WIDE1-1,qAR,N1CALL:>Hi"),
+        ("N2CALL>APRS,WIDE1-1,qAR,N1CALL:>Hi", client("N1CALL", false), "N2CALL>APRS,WIDE1-1,qAO,N1CALL:>Hi"),
+        ("N2CALL>APRS,WIDE1-1:>Hi", client("N1CALL", true), "N2CALL>APRS,WIDE1-1,qAo,N1CALL:>Hi"),
+        ("N2CALL>APRS,WIDE1-1,N1CALL,I:>Hi", client("N1CALL", true), "N2CALL>APRS,WIDE1-1,qAr,N1CALL:>Hi"),
+        ("N2CALL>APRS,WIDE1-1,N1CALL,I:>Hi", client("N1CALL", false), "N2CALL>APRS,WIDE1-1,qAO,N1CALL:>Hi"),
+        // traces collect the servers they pass
+        ("N2CALL>APRS,qAI,T2A:>Hi", client("T2A", true), "N2CALL>APRS,qAI,T2A,T2TEST:>Hi"),
+    ];
+    for &(raw, entry, expected) in &cases {
+        assert_eq!(q_algorithm(raw, entry).unwrap(), expected, "{} {:?}", raw, entry);
+    }
+    assert_eq!(q_algorithm("N1CALL>APRS,qAC,N1CALL:>Hi", Entry::Udp { server: "T2TEST" }).unwrap(),
+               "N1CALL>APRS,qAU,T2TEST:>Hi");
+    assert!(q_algorithm("N1CALL>APRS,qAZ,N1CALL:filter", client("N1CALL", true)).is_err());
+    assert!(q_algorithm("N2CALL>APRS,qAS,T2TEST:>Hi", client("T2B", true)).is_err());
+    assert!(q_algorithm("N2CALL>APRS,qAI,T2A,T2TEST:>Hi", client("T2A", true)).is_err());
+}
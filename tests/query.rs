@@ -0,0 +1,147 @@
+extern crate fap;
+
+use fap::{ObjectReport, OwnedPacket, PacketBuilder, PacketType, PositionReport, Query, QueryResponder, QueryType,
+    StatusReport, Timestamp, WeatherReport};
+use std::time::UNIX_EPOCH;
+
+struct Station {
+    igate: bool,
+}
+
+impl QueryResponder for Station {
+    fn position(&self) -> Option<PositionReport> {
+        Some(PositionReport::new(34.05, -117.2).symbol('/', '#'))
+    }
+
+    fn status(&self) -> Option<StatusReport> {
+        Some(StatusReport::new("Digi on the hill"))
+    }
+
+    fn weather(&self) -> Option<WeatherReport> {
+        Some(WeatherReport::positionless(UNIX_EPOCH).temperature(20.0))
+    }
+
+    fn igate_counts(&self) -> Option<(u32, u32)> {
+        if self.igate { Some((3, 17)) } else { None }
+    }
+
+    fn objects(&self) -> Vec<ObjectReport> {
+        let position = PositionReport::new(34.1, -117.3).symbol('/', 'r');
+        vec![ObjectReport::new("REPEATER", Timestamp::Dhm(UNIX_EPOCH), position)]
+    }
+
+    fn pending_messages(&self, station: &str) -> Vec<String> {
+        vec![format!("N1CALL>APRS::{:9}:Still there?{{05", station)]
+    }
+}
+
+struct Silent;
+
+impl QueryResponder for Silent {}
+
+fn respond(responder: &dyn QueryResponder, raw: &str) -> Vec<String> {
+    let query = Query::parse(raw).unwrap_or_else(|| panic!("{} is not a query", raw));
+    responder.respond(&query, &PacketBuilder::new("N1CALL")).unwrap()
+}
+
+#[test]
+fn general_queries() {
+    let query = Query::parse("N0CALL>APRS,WIDE1-1,qAR,IGATE:?APRS?\r\n").unwrap();
+    assert_eq!(query.kind, QueryType::Aprs);
+    assert_eq!(query.source, "N0CALL");
+    assert_eq!(query.addressee, None);
+    assert_eq!(query.footprint, None);
+    assert_eq!(query.header, "N0CALL>APRS,WIDE1-1,qAR,IGATE");
+
+    let kinds = [("?IGATE?", QueryType::Igate), ("?WX?", QueryType::Weather)];
+    for &(body, kind) in &kinds {
+        assert_eq!(Query::parse(&format!("N0CALL>APRS:{}", body)).unwrap().kind, kind);
+    }
+
+    let footprint = Query::parse("N0CALL>APRS:?APRS? 34.02,-117.15,0200").unwrap().footprint.unwrap();
+    assert_eq!((footprint.latitude, footprint.longitude), (34.02, -117.15));
+    assert!((footprint.radius - 321.8688).abs() < 1e-9);
+    assert!(footprint.contains(34.05, -117.2));
+    assert!(footprint.contains(36.0, -117.15));
+    assert!(!footprint.contains(37.0, -117.15));
+}
+
+#[test]
+fn directed_queries() {
+    let kinds = [
+        ("?APRSP", QueryType::Position), ("?APRSS", QueryType::Status), ("?APRST", QueryType::Trace),
+        ("?PING?", QueryType::Trace), ("?APRSM", QueryType::Messages), ("?APRSO", QueryType::Objects),
+        ("?IGATE?", QueryType::Igate),
+    ];
+    for &(text, kind) in &kinds {
+        let raw = format!("N0CALL>APRS::N1CALL-10:{}", text);
+        let query = Query::parse(&raw).unwrap();
+        assert_eq!(query.kind, kind, "{}", raw);
+        assert_eq!(query.addressee.as_deref(), Some("N1CALL-10"));
+        // libfap agrees that these are messages
+        let packet = OwnedPacket::new(raw.as_str()).unwrap();
+        assert_eq!(packet.packet_type, Some(PacketType::Message));
+    }
+    let query = Query::parse("N0CALL>APRS::N1CALL   :?APRSP{12").unwrap();
+    assert_eq!(query.kind, QueryType::Position);
+    assert_eq!(query.addressee.as_deref(), Some("N1CALL"));
+}
+
+#[test]
+fn not_queries() {
+    let packets = [
+        "N0CALL>APRS:>Status",
+        "N0CALL>APRS:!4903.50N/07201.75W-",
+        "N0CALL>APRS:?APRSP",
+        "N0CALL>APRS:?APRST",
+        "N0CALL>APRS:?PING?",
+        "N0CALL>APRS:?FOO?",
+        "N0CALL>APRS:?APRS? 34.02,-117.15",
+        "N0CALL>APRS:?APRS? 34.02,-117.15,20000",
+        "N0CALL>APRS:?APRS? 95.0,-117.15,0200",
+        "N0CALL>APRS::N1CALL   :Hello",
+        "N0CALL>APRS::N1CALL:?APRSP",
+        ">APRS:?APRS?",
+        "N0CALL:?APRS?",
+        "N0CALL>APRS",
+    ];
+    for raw in &packets {
+        assert_eq!(Query::parse(raw), None, "{}", raw);
+    }
+}
+
+#[test]
+fn replies() {
+    let station = Station { igate: true };
+    assert_eq!(respond(&station, "N0CALL>APRS:?APRS?"), ["N1CALL>APRS:!3403.00N/11712.00W#"]);
+    assert_eq!(respond(&station, "N0CALL>APRS::N1CALL   :?APRSP"), ["N1CALL>APRS:!3403.00N/11712.00W#"]);
+    assert_eq!(respond(&station, "N0CALL>APRS::N1CALL   :?APRSS"), ["N1CALL>APRS:>Digi on the hill"]);
+    assert_eq!(respond(&station, "N0CALL>APRS:?WX?"), ["N1CALL>APRS:_01010000c...s...g...t068"]);
+    assert_eq!(respond(&station, "N0CALL>APRS:?IGATE?"), ["N1CALL>APRS:<IGATE,MSG_CNT=3,LOC_CNT=17"]);
+    assert_eq!(respond(&station, "N0CALL>APRS::N1CALL   :?APRSO"),
+               ["N1CALL>APRS:;REPEATER *010000z3406.00N/11718.00Wr"]);
+    assert_eq!(respond(&station, "N0CALL>APRS::N1CALL   :?APRSM"), ["N1CALL>APRS::N0CALL   :Still there?{05"]);
+    assert_eq!(respond(&station, "N0CALL>APRS,WIDE1-1*,WIDE2-1::N1CALL   :?PING?"),
+               ["N1CALL>APRS::N0CALL   :N0CALL>APRS,WIDE1-1*,WIDE2-1"]);
+
+    // a long route does not fit in one message
+    let raw = "N0CALL-15>APRS,N2CALL-15*,N3CALL-15*,N4CALL-15*,N5CALL-15*,N6CALL-15*,WIDE2*::N1CALL   :?APRST";
+    let replies = respond(&station, raw);
+    assert_eq!(replies.len(), 2);
+    assert!(replies.iter().all(|r| r.starts_with("N1CALL>APRS::N0CALL-15:")));
+
+    assert!(respond(&Station { igate: false }, "N0CALL>APRS:?IGATE?").is_empty());
+    assert!(respond(&Silent, "N0CALL>APRS:?APRS?").is_empty());
+    assert!(respond(&Silent, "N0CALL>APRS::N1CALL   :?APRSO").is_empty());
+    assert_eq!(respond(&Silent, "N0CALL>APRS::N1CALL   :?PING?").len(), 1);
+}
+
+#[test]
+fn queries_for_others() {
+    let station = Station { igate: true };
+    assert!(respond(&station, "N0CALL>APRS::N2CALL   :?APRSP").is_empty());
+    assert!(respond(&station, "N1CALL>APRS:?APRS?").is_empty());
+    assert_eq!(respond(&station, "N0CALL>APRS:?APRS? 34.02,-117.15,0020").len(), 1);
+    assert!(respond(&station, "N0CALL>APRS:?APRS? 40.0,-100.0,0200").is_empty());
+    assert!(respond(&Silent, "N0CALL>APRS::N2CALL   :?PING?").is_empty());
+}
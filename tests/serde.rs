@@ -0,0 +1,57 @@
+#![cfg(feature = "serde")]
+
+extern crate fap;
+extern crate serde_json;
+
+use fap::OwnedPacket;
+use serde_json::Value;
+
+#[test]
+fn perl_compatible_keys() {
+    let raw = "DISCOF>APT314,RAZOR*,WIDE1*,WIDE2-1,qAS,GERLCH:/022526h4046.40N/11912.12W-347/001/KG6YJN";
+    let owned = OwnedPacket::new(raw).unwrap();
+    let json: Value = serde_json::to_value(&owned).unwrap();
+
+    assert_eq!(json["type"], "location");
+    assert_eq!(json["format"], "uncompressed");
+    assert_eq!(json["srccallsign"], "DISCOF");
+    assert_eq!(json["dstcallsign"], "APT314");
+    assert_eq!(json["digipeaters"][0]["call"], "RAZOR");
+    assert_eq!(json["digipeaters"][0]["wasdigied"], true);
+    assert_eq!(json["digipeaters"][2]["wasdigied"], false);
+    assert_eq!(json["symboltable"], "/");
+    assert_eq!(json["symbolcode"], "-");
+    assert_eq!(json["course"], 347);
+    assert!(json.get("wx").is_none());
+    assert!(json.get("altitude").is_none());
+}
+
+#[test]
+fn weather_and_telemetry_keys() {
+    let wx = OwnedPacket::new("CW7293>APRS,TCPXX*,qAX,CWOP-4:@080812z3718.28N/12158.90W_224/001g002t055r000p000P000h76b10174eWUHU216DAVISVP2.").unwrap();
+    let json: Value = serde_json::to_value(&wx).unwrap();
+    assert_eq!(json["wx"]["wind_direction"], 224);
+    assert_eq!(json["wx"]["humidity"], 76);
+
+    let tlm = OwnedPacket::new("N0CALL>APRS:T#005,199,000,255,073,123,01101001").unwrap();
+    let json: Value = serde_json::to_value(&tlm).unwrap();
+    assert_eq!(json["type"], "telemetry");
+    assert_eq!(json["telemetry"]["seq"], 5);
+    assert_eq!(json["telemetry"]["vals"][0], 199.0);
+    assert_eq!(json["telemetry"]["bits"], "01101001");
+}
+
+#[test]
+fn round_trip() {
+    let raws = [
+        "VK2YCJ-9>S2U1Q2,VK2RTZ-1*,WIDE2-2,qAR,VK2ZEN-5:\x60OE p#!>/",
+        "N0CALL>APRS:<IGATE,MSG_CNT=10,LOC_CNT=3",
+        "N0CALL>APRS::BLN1     :Net tonight at 8pm{42",
+    ];
+    for raw in raws.iter() {
+        let owned = OwnedPacket::new(*raw).unwrap();
+        let json = serde_json::to_string(&owned).unwrap();
+        let back: OwnedPacket = serde_json::from_str(&json).unwrap();
+        assert_eq!(owned, back, "round trip of {:?}", raw);
+    }
+}
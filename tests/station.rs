@@ -0,0 +1,188 @@
+extern crate fap;
+
+use fap::aprsis::{Filter, FilterContext};
+use fap::station::StationDb;
+use fap::{CommentMode, Packet, Parser};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(1_500_000_000 + secs)
+}
+
+fn ingest(db: &mut StationDb, raw: &str, now: SystemTime) {
+    db.ingest(&Packet::new(raw).unwrap(), now);
+}
+
+#[test]
+fn track() {
+    let mut db = StationDb::new().track_len(2);
+    ingest(&mut db, "N1CALL-9>APRS:!4903.50N/07201.75W>088/036/A=001234First", at(0));
+    let station = db.get("N1CALL-9").unwrap();
+    let position = station.position().unwrap();
+    assert_eq!(position.time, at(0));
+    assert!((position.longitude + 72.0291667).abs() < 1e-6);
+    assert_eq!(position.course, Some(88));
+    assert!((position.speed.unwrap() - 66.672).abs() < 1e-3);
+    assert!((position.altitude.unwrap() - 376.1232).abs() < 1e-3);
+    assert_eq!((station.symbol_table, station.symbol_code), (Some('/'), Some('>')));
+    assert_eq!(station.comment.as_deref(), Some("First"));
+    assert!(!station.is_object());
+
+    // standing still only refreshes the last point
+    ingest(&mut db, "N1CALL-9>APRS:!4903.50N/07201.75W>Second", at(10));
+    assert_eq!(db.get("N1CALL-9").unwrap().track.len(), 1);
+    assert_eq!(db.get("N1CALL-9").unwrap().position().unwrap().time, at(10));
+    ingest(&mut db, "N1CALL-9>APRS:!4904.50N/07201.75W>", at(20));
+    ingest(&mut db, "N1CALL-9>APRS:!4905.50N/07201.75W>", at(30));
+    let station = db.get("N1CALL-9").unwrap();
+    assert_eq!(station.track.len(), 2);
+    assert!((station.track[0].latitude - 49.075).abs() < 1e-6);
+    assert!((station.track[1].latitude - 49.0916667).abs() < 1e-6);
+    assert_eq!(station.last_heard, at(30));
+    assert_eq!(station.comment, None);
+
+    // a packet timestamp is the time of the position
+    ingest(&mut db, "N2CALL>APRS:/092345z4903.50N/07201.75W>", at(0));
+    assert!(db.get("N2CALL").unwrap().position().unwrap().time != at(0));
+}
+
+#[test]
+fn state() {
+    let mut db = StationDb::new();
+    let packets = [
+        "N1CALL>APRS:>Net at 8pm",
+        "N2CALL>APRS:!4903.50N/07201.75W_220/004g005t077r000p000P000h50b09900",
+        "N3CALL>APRS:T#005,199,000,255,073,123,01101001",
+        "N4CALL>APRS::N3CALL   :PARM.Battery,Btemp",
+        "N4CALL>APRS::N3CALL   :EQNS.0,0.075,0,0,10,0",
+        "N5CALL>APRS:!4903.50N/07201.75W#PHG5132",
+        "N0CALL-10>APRS,WIDE1-1:}N6CALL>APRS,TCPIP,N0CALL-10*:>Inside",
+    ];
+    for raw in &packets {
+        ingest(&mut db, raw, at(0));
+    }
+    assert_eq!(db.get("N1CALL").unwrap().status.as_deref(), Some("Net at 8pm"));
+    assert_eq!(db.get("N1CALL").unwrap().position(), None);
+
+    let (weather, time) = db.get("N2CALL").unwrap().weather.clone().unwrap();
+    assert_eq!(time, at(0));
+    assert!((weather.temp.unwrap() - 25.0).abs() < 0.1);
+    assert!(db.get("N2CALL").unwrap().position().is_some());
+
+    let n3call = db.get("N3CALL").unwrap();
+    assert_eq!(n3call.telemetry.as_ref().unwrap().0.seq, Some(5));
+    let metadata = n3call.telemetry_metadata.as_ref().unwrap();
+    assert_eq!(metadata.names, ["Battery", "Btemp"]);
+    assert_eq!(metadata.coefficients[..2], [(0.0, 0.075, 0.0), (0.0, 10.0, 0.0)]);
+    assert_eq!(db.get("N4CALL").unwrap().telemetry_metadata, None);
+    let scaled = n3call.scaled_telemetry().unwrap();
+    assert_eq!(scaled.values[0].name.as_deref(), Some("Battery"));
+    assert!((scaled.values[1].value - 0.0).abs() < 1e-9);
+    assert!((scaled.values[3].value - 73.0).abs() < 1e-9);
+    assert_eq!(db.get("N1CALL").unwrap().telemetry_metadata, None);
+    assert_eq!(db.get("N1CALL").unwrap().scaled_telemetry(), None);
+
+    let n5call = db.get("N5CALL").unwrap();
+    assert_eq!(n5call.phg.as_deref(), Some("5132"));
+    // 25 W, 20 ft, 3 dB: 7.95 miles
+    assert!((n5call.range.unwrap() - 12.80).abs() < 0.01);
+
+    assert_eq!(db.get("N6CALL").unwrap().status.as_deref(), Some("Inside"));
+    assert!(db.get("N0CALL-10").is_none());
+    assert_eq!(db.len(), 6);
+}
+
+#[test]
+fn objects() {
+    let mut db = StationDb::new();
+    ingest(&mut db, "N1CALL>APRS:;LEADER   *092345z4903.50N/07201.75W>088/036", at(0));
+    ingest(&mut db, "N1CALL>APRS:)AID #2!4903.50N/07201.75WA", at(0));
+    let leader = db.object("LEADER").unwrap();
+    assert_eq!(leader.owner.as_deref(), Some("N1CALL"));
+    assert!(leader.is_object());
+    assert!(db.object("AID #2").is_some());
+    assert!(db.get("LEADER").is_none());
+    // the owner was heard too
+    assert!(db.get("N1CALL").unwrap().position().is_none());
+
+    // taken over by another station
+    ingest(&mut db, "N2CALL>APRS:;LEADER   *092345z4904.50N/07201.75W>088/036", at(10));
+    assert_eq!(db.object("LEADER").unwrap().owner.as_deref(), Some("N2CALL"));
+    assert_eq!(db.object("LEADER").unwrap().track.len(), 2);
+
+    ingest(&mut db, "N2CALL>APRS:;LEADER   _092345z4904.50N/07201.75W>", at(20));
+    ingest(&mut db, "N1CALL>APRS:)AID #2_4903.50N/07201.75WA", at(20));
+    assert!(db.object("LEADER").is_none());
+    assert!(db.object("AID #2").is_none());
+    assert_eq!(db.len(), 2);
+    assert!(db.get("N2CALL").is_some());
+}
+
+#[test]
+fn expiry() {
+    let mut db = StationDb::new().max_age(Duration::from_secs(60));
+    ingest(&mut db, "N1CALL>APRS:>Early", at(0));
+    ingest(&mut db, "N1CALL>APRS:;LEADER   *092345z4903.50N/07201.75W>", at(0));
+    ingest(&mut db, "N2CALL>APRS:>Later", at(60));
+    assert_eq!(db.len(), 3);
+    ingest(&mut db, "N2CALL>APRS:>Later", at(61));
+    assert_eq!(db.len(), 1);
+    db.expire(at(200));
+    assert!(db.is_empty());
+
+    // stations heard again are kept, whatever order the times come in
+    ingest(&mut db, "N1CALL>APRS:>Early", at(300));
+    ingest(&mut db, "N2CALL>APRS:>Late", at(350));
+    ingest(&mut db, "N1CALL>APRS:>Again", at(340));
+    for i in 0..10 {
+        ingest(&mut db, "N3CALL>APRS:>Busy", at(301 + i));
+    }
+    db.expire(at(370));
+    assert_eq!(db.len(), 3);
+    db.expire(at(371));
+    assert!(db.get("N1CALL").is_some() && db.get("N3CALL").is_none());
+    db.expire(at(401));
+    assert_eq!(db.len(), 1);
+    assert!(db.get("N2CALL").is_some());
+}
+
+#[test]
+fn queries() {
+    let mut db = StationDb::new();
+    ingest(&mut db, "N1CALL>APRS:!4903.50N/07201.75W>", at(0));
+    ingest(&mut db, "N2CALL>APRS:!4903.50N/17930.00E>", at(0));
+    ingest(&mut db, "N3CALL>APRS:!4903.50S/17930.00W>", at(0));
+    ingest(&mut db, "N4CALL>APRS:>No position", at(0));
+    ingest(&mut db, "N4CALL>APRS:;OBJECT   *092345z4903.50N/07210.00W>", at(0));
+
+    let names = |mut stations: Vec<&fap::station::Station>| {
+        stations.sort_by(|a, b| a.name.cmp(&b.name));
+        stations.iter().map(|s| s.name.clone()).collect::<Vec<_>>()
+    };
+    assert_eq!(names(db.within(49.0, -73.0, 50.0, -72.0)), ["N1CALL", "OBJECT"]);
+    assert_eq!(names(db.within(-90.0, 179.0, 90.0, -179.0)), ["N2CALL", "N3CALL"]);
+    assert!(db.within(0.0, 0.0, 10.0, 10.0).is_empty());
+    assert_eq!(db.iter().count(), 5);
+
+    let (latitude, longitude) = db.position_of("N1CALL").unwrap();
+    assert!((latitude - 49.0583333).abs() < 1e-6 && (longitude + 72.0291667).abs() < 1e-6);
+    assert_eq!(db.position_of("N4CALL"), None);
+    let filter = Filter::parse("f/N1CALL/10").unwrap();
+    let near = Packet::new("N5CALL>APRS:!4903.00N/07201.00W>").unwrap();
+    assert!(filter.matches_with(&near, &db));
+    assert!(!filter.matches(&near));
+}
+
+#[test]
+fn third_party_with_parser() {
+    let raw = "N0CALL-10>APRS,WIDE1-1:}N1CALL>APRS,TCPIP,N0CALL-10*:!4903.50N/07201.75W>  Spaced out  ";
+    let parser = Parser::builder().comment_mode(CommentMode::PerlCompatible).build();
+    let mut db = StationDb::new();
+    db.ingest_with(&parser, &parser.parse(raw).unwrap(), at(0));
+    assert_eq!(db.get("N1CALL").unwrap().comment.as_deref(), Some("Spaced out"));
+    assert!(db.get("N0CALL-10").is_none());
+
+    let mut db = StationDb::new();
+    ingest(&mut db, raw, at(0));
+    assert_eq!(db.get("N1CALL").unwrap().comment.as_deref(), Some("  Spaced out  "));
+}
@@ -0,0 +1,80 @@
+extern crate fap;
+
+use fap::{Error, OwnedPacket, PacketBuilder, PacketType, Parser, StatusReport};
+use std::time::{Duration, UNIX_EPOCH};
+
+fn parse(raw: &str) -> OwnedPacket {
+    OwnedPacket::new(raw).unwrap_or_else(|e| panic!("{}: {}", raw, e))
+}
+
+#[test]
+fn status() {
+    let raw = PacketBuilder::new("N0CALL").status(&StatusReport::new("On the air")).unwrap();
+    assert_eq!(raw, "N0CALL>APRS:>On the air");
+    let packet = parse(&raw);
+    assert_eq!(packet.packet_type, Some(PacketType::Status));
+    assert_eq!(packet.status.as_deref(), Some("On the air"));
+
+    let time = UNIX_EPOCH + Duration::from_secs(1528597526);
+    let raw = PacketBuilder::new("N0CALL").status(&StatusReport::new("QRV 145.500").timestamp(time)).unwrap();
+    assert_eq!(raw, "N0CALL>APRS:>100225zQRV 145.500");
+    let parser = Parser::builder().reference_time(time + Duration::from_secs(60)).build();
+    let packet = parser.parse(raw).unwrap().to_owned();
+    assert_eq!(packet.timestamp, Some(1528597500));
+    assert_eq!(packet.status.as_deref(), Some("QRV 145.500"));
+}
+
+#[test]
+fn grid_and_beam() {
+    let builder = PacketBuilder::new("N0CALL");
+    let raw = builder.status(&StatusReport::new("Contest").grid("IO91", '\\', 'K')).unwrap();
+    assert_eq!(raw, "N0CALL>APRS:>IO91\\K Contest");
+    assert_eq!(parse(&raw).status.as_deref(), Some("IO91\\K Contest"));
+
+    let beams = [(0, 10.0, "^01"), (355, 12250.0, "^0Z"), (90, 0.0, "^90"), (275, 1000.0, "^SA")];
+    for &(heading, erp, suffix) in &beams {
+        let raw = builder.status(&StatusReport::new("Beam").beam(heading, erp)).unwrap();
+        assert_eq!(raw, format!("N0CALL>APRS:>Beam{}", suffix));
+    }
+}
+
+#[test]
+fn invalid_status() {
+    let builder = PacketBuilder::new("N0CALL");
+    let rejected = |report: StatusReport| match builder.status(&report) {
+        Err(Error::Encode(_)) => {},
+        other => panic!("{:?} not rejected: {:?}", report, other),
+    };
+    rejected(StatusReport::new("a|b"));
+    rejected(StatusReport::new("a~b"));
+    rejected(StatusReport::new("a\nb"));
+    rejected(StatusReport::new(&"x".repeat(63)));
+    rejected(StatusReport::new(&"x".repeat(56)).timestamp(UNIX_EPOCH));
+    rejected(StatusReport::new("x").grid("IO91SX", '/', 'G').timestamp(UNIX_EPOCH));
+    rejected(StatusReport::new("x").grid("IO9", '/', 'G'));
+    rejected(StatusReport::new("x").grid("ZZ91", '/', 'G'));
+    rejected(StatusReport::new("x").grid("IO91SX", 'x', 'G'));
+    rejected(StatusReport::new("x").beam(0, 20000.0));
+    assert!(builder.status(&StatusReport::new(&"x".repeat(62))).is_ok());
+    assert!(builder.status(&StatusReport::new(&"x".repeat(55)).timestamp(UNIX_EPOCH)).is_ok());
+}
+
+#[test]
+fn capabilities() {
+    let builder = PacketBuilder::new("N0CALL-10");
+    let raw = builder.igate_capabilities(12, 34).unwrap();
+    assert_eq!(raw, "N0CALL-10>APRS:<IGATE,MSG_CNT=12,LOC_CNT=34");
+    let packet = parse(&raw);
+    assert_eq!(packet.packet_type, Some(PacketType::Capabilities));
+    assert_eq!(packet.capabilities.get("IGATE"), Some(&None));
+    assert_eq!(packet.capabilities.get("MSG_CNT"), Some(&Some("12".to_string())));
+    assert_eq!(packet.capabilities.get("LOC_CNT"), Some(&Some("34".to_string())));
+
+    let raw = builder.capabilities(&[("IGATE", None), ("FILTER", Some("m/50"))]).unwrap();
+    assert_eq!(raw, "N0CALL-10>APRS:<IGATE,FILTER=m/50");
+    assert!(builder.capabilities(&[]).is_err());
+    assert!(builder.capabilities(&[("A,B", None)]).is_err());
+    assert!(builder.capabilities(&[("A=B", None)]).is_err());
+    assert!(builder.capabilities(&[("A", Some("1,2"))]).is_err());
+    assert!(builder.capabilities(&[("", Some("1"))]).is_err());
+}
@@ -0,0 +1,202 @@
+#[macro_use] extern crate approx;
+extern crate fap;
+
+use fap::{Error, OwnedPacket, Packet, PacketBuilder, PacketType, PositionReport, TelemetryDefinition, TelemetryRegistry,
+    TelemetryReport};
+
+fn parse(raw: &str) -> OwnedPacket {
+    OwnedPacket::new(raw).unwrap_or_else(|e| panic!("{}: {}", raw, e))
+}
+
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+#[test]
+fn reports() {
+    let report = TelemetryReport::new(42, &[13.8, 4.25, 120.0, 0.0, 999.0]).bits(0b1000_0011);
+    let raw = PacketBuilder::new("SOLAR1").telemetry(&report).unwrap();
+    assert_eq!(raw, "SOLAR1>APRS:T#042,13.8,4.25,120,000,999,11000001");
+    let packet = parse(&raw);
+    assert_eq!(packet.packet_type, Some(PacketType::Telemetry));
+    let telemetry = packet.telemetry.unwrap();
+    assert_eq!(telemetry.seq, Some(42));
+    assert_eq!(telemetry.values, [Some(13.8), Some(4.25), Some(120.0), Some(0.0), Some(999.0)]);
+    assert_eq!(telemetry.bits.as_deref(), Some("11000001"));
+
+    let report = TelemetryReport::new(999, &[-12.5, 1000.0, 123456.0]).comment(" Site A");
+    let raw = PacketBuilder::new("SOLAR1").telemetry(&report).unwrap();
+    assert_eq!(raw, "SOLAR1>APRS:T#999,-12.5,1000,123456,,,00000000 Site A");
+    let telemetry = parse(&raw).telemetry.unwrap();
+    assert_abs_diff_eq!(telemetry.values[0].unwrap(), -12.5);
+    assert_abs_diff_eq!(telemetry.values[2].unwrap(), 123456.0);
+    assert_eq!(telemetry.bits.as_deref(), Some("00000000"));
+}
+
+#[test]
+fn invalid_reports() {
+    let builder = PacketBuilder::new("SOLAR1");
+    let rejected = |report: TelemetryReport| match builder.telemetry(&report) {
+        Err(Error::Encode(_)) => {},
+        other => panic!("{:?} not rejected: {:?}", report, other),
+    };
+    rejected(TelemetryReport::new(1000, &[1.0]));
+    rejected(TelemetryReport::new(1, &[1.0; 6]));
+    rejected(TelemetryReport::new(1, &[1234567.0]));
+    rejected(TelemetryReport::new(1, &[0.123456789]));
+    rejected(TelemetryReport::new(1, &[f64::NAN]));
+    rejected(TelemetryReport::new(1, &[1.0]).comment("a\nb"));
+}
+
+#[test]
+fn comment_telemetry() {
+    let report = PositionReport::new(49.0583, -72.0292).comment("Solar").telemetry(7, &[100, 8280], None);
+    let raw = PacketBuilder::new("SOLAR1").position(&report).unwrap();
+    assert_eq!(raw, "SOLAR1>APRS:!4903.50N/07201.75W/Solar|!(\"*{{|");
+    let packet = parse(&raw);
+    assert_eq!(packet.comment.as_deref(), Some("Solar"));
+    let telemetry = packet.telemetry.unwrap();
+    assert_eq!(telemetry.seq, Some(7));
+    assert_eq!(telemetry.values, [Some(100.0), Some(8280.0), None, None, None]);
+
+    let report = report.telemetry(8280, &[1, 2, 3, 4, 5], Some(0b0100_0001)).compressed(true);
+    let telemetry = parse(&PacketBuilder::new("SOLAR1").position(&report).unwrap()).telemetry.unwrap();
+    assert_eq!(telemetry.seq, Some(8280));
+    assert_eq!(telemetry.values, [Some(1.0), Some(2.0), Some(3.0), Some(4.0), Some(5.0)]);
+    assert_eq!(telemetry.bits.as_deref(), Some("10000010"));
+
+    let builder = PacketBuilder::new("SOLAR1");
+    let base = PositionReport::new(49.0583, -72.0292);
+    assert!(builder.position(&base.clone().telemetry(1, &[], None)).is_err());
+    assert!(builder.position(&base.clone().telemetry(1, &[1, 2, 3, 4, 5, 6], None)).is_err());
+    assert!(builder.position(&base.clone().telemetry(1, &[1, 2, 3], Some(1))).is_err());
+    assert!(builder.position(&base.clone().telemetry(1, &[8281], None)).is_err());
+}
+
+#[test]
+fn definitions() {
+    let builder = PacketBuilder::new("SOLAR1");
+    let definitions = [
+        (TelemetryDefinition::Parameters(names(&["Vbat", "Vsol", "Ibat", "Temp", "Light", "Door", "Fan"])),
+         ":SOLAR1   :PARM.Vbat,Vsol,Ibat,Temp,Light,Door,Fan"),
+        (TelemetryDefinition::Units(names(&["V", "V", "A", "degC"])),
+         ":SOLAR1   :UNIT.V,V,A,degC"),
+        (TelemetryDefinition::Equations(vec![(0.0, 0.1, 0.0), (0.0, 0.1, 0.0), (0.0, 0.01, -2.5)]),
+         ":SOLAR1   :EQNS.0,0.1,0,0,0.1,0,0,0.01,-2.5"),
+        (TelemetryDefinition::Bits(0b0000_0011, "Solar site A".to_string()),
+         ":SOLAR1   :BITS.11000000,Solar site A"),
+    ];
+    for &(ref definition, body) in &definitions {
+        let raw = builder.telemetry_definition(definition).unwrap();
+        assert_eq!(raw, format!("SOLAR1>APRS:{}", body));
+        let packet = parse(&raw);
+        assert_eq!(packet.packet_type, Some(PacketType::TelemetryMessage), "{}", raw);
+        assert_eq!(packet.destination.as_deref(), Some("SOLAR1"));
+        assert_eq!(packet.message.as_deref(), Some(&body[11..]));
+    }
+}
+
+#[test]
+fn invalid_definitions() {
+    let builder = PacketBuilder::new("SOLAR1");
+    let rejected = |definition: TelemetryDefinition| match builder.telemetry_definition(&definition) {
+        Err(Error::Encode(_)) => {},
+        other => panic!("{:?} not rejected: {:?}", definition, other),
+    };
+    rejected(TelemetryDefinition::Parameters(Vec::new()));
+    rejected(TelemetryDefinition::Parameters(names(&["A"; 14])));
+    rejected(TelemetryDefinition::Parameters(names(&["Battery"; 3])));
+    rejected(TelemetryDefinition::Units(names(&["V", "V", "V", "V", "V", "V", "Amps12"])));
+    rejected(TelemetryDefinition::Units(names(&["a,b"])));
+    rejected(TelemetryDefinition::Units(names(&["a|b"])));
+    rejected(TelemetryDefinition::Equations(Vec::new()));
+    rejected(TelemetryDefinition::Equations(vec![(0.0, 1.0, 0.0); 6]));
+    rejected(TelemetryDefinition::Equations(vec![(0.0, f64::INFINITY, 0.0)]));
+    rejected(TelemetryDefinition::Equations(vec![(0.000123456789, 0.000123456789, 0.000123456789); 5]));
+    rejected(TelemetryDefinition::Bits(0, "This title is far too long".to_string()));
+
+    // B5 takes 4 characters, B6 only 3
+    let mut fifth_bit = names(&["A"; 10]);
+    fifth_bit[9] = "Pump".to_string();
+    assert!(builder.telemetry_definition(&TelemetryDefinition::Parameters(fifth_bit.clone())).is_ok());
+    fifth_bit[9] = "Pumps".to_string();
+    rejected(TelemetryDefinition::Parameters(fifth_bit));
+    let mut sixth_bit = names(&["A"; 11]);
+    sixth_bit[10] = "Fan".to_string();
+    assert!(builder.telemetry_definition(&TelemetryDefinition::Parameters(sixth_bit.clone())).is_ok());
+    sixth_bit[10] = "Fans".to_string();
+    rejected(TelemetryDefinition::Parameters(sixth_bit));
+}
+
+#[test]
+fn decode_definitions() {
+    let builder = PacketBuilder::new("SOLAR1");
+    let definitions = [
+        TelemetryDefinition::Parameters(names(&["Vbat", "Vsol", "Ibat", "Temp", "Light", "Door", "Fan"])),
+        TelemetryDefinition::Units(names(&["V", "V", "A", "degC"])),
+        TelemetryDefinition::Equations(vec![(0.0, 0.1, 0.0), (0.0, 0.1, 0.0), (0.0, 0.01, -2.5)]),
+        TelemetryDefinition::Bits(0b0000_0011, "Solar site A".to_string()),
+    ];
+    for definition in &definitions {
+        let raw = builder.telemetry_definition(definition).unwrap();
+        assert_eq!(Packet::new(raw).unwrap().telemetry_definition().as_ref(), Some(definition));
+    }
+
+    let packet = Packet::new("AA7GT>APMI06,TCPIP*,qAC,T2NALA::AA7GT    :UNIT.Volt,Pkt,Pkt,Pcnt,None,On,On,On,On,Hi,Hi,Hi,Hi").unwrap();
+    assert_eq!(packet.telemetry_definition(),
+               Some(TelemetryDefinition::Units(names(&["Volt", "Pkt", "Pkt", "Pcnt", "None", "On", "On", "On", "On", "Hi", "Hi", "Hi", "Hi"]))));
+    assert_eq!(TelemetryDefinition::parse("PARM.,,Temp"), Some(TelemetryDefinition::Parameters(names(&["", "", "Temp"]))));
+    assert_eq!(TelemetryDefinition::parse("EQNS.0,2,1,0,5"), Some(TelemetryDefinition::Equations(vec![(0.0, 2.0, 1.0), (0.0, 5.0, 0.0)])));
+    assert_eq!(TelemetryDefinition::parse("BITS.11111111"), Some(TelemetryDefinition::Bits(0xff, String::new())));
+    assert_eq!(TelemetryDefinition::parse("EQNS.0,x,0"), None);
+    assert_eq!(TelemetryDefinition::parse("EQNS.0,1,0,0,1,0,0,1,0,0,1,0,0,1,0,0"), None);
+    assert_eq!(TelemetryDefinition::parse("BITS.10201111,Title"), None);
+    assert_eq!(TelemetryDefinition::parse("BITS.111111111,Title"), None);
+    assert_eq!(TelemetryDefinition::parse("Hello"), None);
+    assert_eq!(Packet::new("SOLAR1>APRS:>PARM.Vbat").unwrap().telemetry_definition(), None);
+}
+
+#[test]
+fn registry() {
+    let mut registry = TelemetryRegistry::new();
+    let report = Packet::new("SOLAR1>APRS:T#042,138,250,73,,,10100000").unwrap();
+    let unscaled = registry.ingest(&report).unwrap();
+    // libfap reports the empty values as 0
+    assert_eq!(unscaled.values.iter().map(|v| v.value).collect::<Vec<_>>(), [138.0, 250.0, 73.0, 0.0, 0.0]);
+    assert!(unscaled.bits.iter().take(3).map(|b| b.on).eq(vec![true, false, true]));
+    assert_eq!(unscaled.values[0].name, None);
+
+    let metadata = [
+        "SOLAR1>APRS::SOLAR1   :PARM.Vbat,Vsol,Temp,,,Door,Fan",
+        "SOLAR1>APRS::SOLAR1   :UNIT.V,V,degC,,,open,on",
+        "SOLAR1>APRS::SOLAR1   :EQNS.0,0.1,0,0,0.1,0,0.01,-1,-40",
+        "SOLAR1>APRS::SOLAR1   :BITS.01111111,Solar site A",
+    ];
+    for raw in &metadata {
+        assert_eq!(registry.ingest(&Packet::new(*raw).unwrap()), None);
+    }
+    assert!(registry.metadata("solar1").is_some());
+    assert!(registry.metadata("SOLAR2").is_none());
+    assert_eq!(registry.ingest(&Packet::new("SOLAR1>APRS:>Status").unwrap()), None);
+
+    let scaled = registry.ingest(&report).unwrap();
+    assert_eq!(scaled.seq, Some(42));
+    assert_eq!(scaled.project.as_deref(), Some("Solar site A"));
+    let values: Vec<_> = scaled.values.iter().take(3).map(|v| (v.channel, v.name.as_deref(), v.unit.as_deref())).collect();
+    assert_eq!(values, [(1, Some("Vbat"), Some("V")), (2, Some("Vsol"), Some("V")), (3, Some("Temp"), Some("degC"))]);
+    assert_relative_eq!(scaled.values[0].value, 13.8, epsilon = 1e-9);
+    assert_relative_eq!(scaled.values[1].value, 25.0, epsilon = 1e-9);
+    assert_relative_eq!(scaled.values[2].value, 0.01 * 73.0 * 73.0 - 73.0 - 40.0, epsilon = 1e-9);
+
+    assert_eq!(scaled.bits.len(), 8);
+    let door = &scaled.bits[0];
+    assert_eq!((door.bit, door.name.as_deref(), door.label.as_deref(), door.on), (1, Some("Door"), Some("open"), false));
+    let fan = &scaled.bits[1];
+    assert_eq!((fan.bit, fan.name.as_deref(), fan.on), (2, Some("Fan"), false));
+    assert!(scaled.bits[2].on && scaled.bits[2].name.is_none());
+
+    // metadata sent by another station, for this one
+    registry.ingest(&Packet::new("N0CALL>APRS::SOLAR2   :EQNS.0,2,0").unwrap());
+    let report = Packet::new("SOLAR2>APRS:T#001,21,0,0,0,0,00000000").unwrap();
+    assert_relative_eq!(registry.ingest(&report).unwrap().values[0].value, 42.0);
+}
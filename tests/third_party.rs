@@ -0,0 +1,96 @@
+extern crate aprs;
+extern crate fap;
+
+use aprs::Packet as AprsPacket;
+use fap::{CommentMode, Error, Packet, PacketBuilder, PacketType, Parser};
+
+#[test]
+fn decapsulation() {
+    let raw = "N0CALL-10>APZ001,WIDE1-1*:}N1CALL-9>APRS,TCPIP,N0CALL-10*:!4903.50N/07201.75W>088/036Mobile";
+    let outer = Packet::new(raw).unwrap();
+    assert_eq!(outer.packet_type(), None);
+    assert_eq!(outer.source(), "N0CALL-10");
+
+    let inner = outer.third_party().unwrap().unwrap();
+    assert_eq!(inner.source(), "N1CALL-9");
+    assert_eq!(inner.packet_type(), Some(PacketType::Location));
+    assert_eq!(inner.header(), "N1CALL-9>APRS,TCPIP,N0CALL-10*");
+    assert_eq!(inner.comment().unwrap(), "Mobile");
+    assert_eq!(inner.third_party_header(), Some("N0CALL-10>APZ001,WIDE1-1*"));
+    assert!(inner.third_party().is_none());
+
+    let owned = inner.to_owned();
+    assert_eq!(owned.src_callsign, "N1CALL-9");
+    assert!((owned.latitude.unwrap() - 49.0583333).abs() < 1e-6);
+    assert_eq!(owned.third_party_header.as_deref(), Some("N0CALL-10>APZ001,WIDE1-1*"));
+    assert_eq!(outer.to_owned().third_party_header, None);
+}
+
+#[test]
+fn not_third_party() {
+    let packet = Packet::new("N1CALL>APRS:>Status").unwrap();
+    assert!(packet.third_party().is_none());
+    assert_eq!(packet.third_party_header(), None);
+}
+
+#[test]
+fn nested() {
+    let raw = "N0CALL>APRS:}N1CALL>APRS,TCPIP,N0CALL*:}N2CALL>APRS,GATE*:>Far away";
+    let first = Packet::new(raw).unwrap().third_party().unwrap().unwrap();
+    assert_eq!(first.source(), "N1CALL");
+    let second = first.third_party().unwrap().unwrap();
+    assert_eq!(second.source(), "N2CALL");
+    assert_eq!(second.status().unwrap(), "Far away");
+    assert_eq!(second.third_party_header(), Some("N1CALL>APRS,TCPIP,N0CALL*"));
+}
+
+#[test]
+fn invalid_inner() {
+    let packet = Packet::new("N0CALL>APRS:}no header here").unwrap();
+    assert!(packet.third_party().unwrap().is_err());
+}
+
+#[test]
+fn round_trip() {
+    let builder = PacketBuilder::new("N0CALL-10").path(&["WIDE1-1"]);
+    let raw = builder.third_party("N1CALL>APRS,TCPIP*,qAC,T2TEST::N2CALL   :Hi{1").unwrap();
+    let inner = Packet::new(raw).unwrap().third_party().unwrap().unwrap();
+    assert_eq!(inner.packet_type(), Some(PacketType::Message));
+    assert_eq!(inner.source(), "N1CALL");
+    assert_eq!(inner.message_destination().unwrap().trim_end(), "N2CALL");
+    assert_eq!(inner.message().unwrap(), "Hi");
+}
+
+#[test]
+fn parser_dropped() {
+    let parser = Parser::new();
+    let outer = parser.parse("N0CALL>APRS:}N1CALL>APRS,TCPIP,N0CALL*:!4903.50N/07201.75W-Hi").unwrap();
+    drop(parser);
+    let inner = outer.third_party().unwrap().unwrap();
+    assert_eq!(inner.source(), "N1CALL");
+    assert_eq!(inner.latitude(), Some(49.0583333));
+}
+
+#[test]
+fn parser_options() {
+    let raw = "N0CALL>APRS:}N1CALL>APRS,TCPIP,N0CALL*:!4903.50N/07201.75W- Hi ";
+    let parser = Parser::builder()
+        .comment_mode(CommentMode::PerlCompatible)
+        .accept(&[PacketType::Location])
+        .keep_original(true)
+        .build();
+    let outer = Packet::new(raw).unwrap();
+    let inner = parser.third_party(&outer).unwrap().unwrap();
+    assert_eq!(inner.comment().unwrap(), "Hi");
+    assert_eq!(inner.orig_packet().unwrap(), "N1CALL>APRS,TCPIP,N0CALL*:!4903.50N/07201.75W- Hi ");
+    assert_eq!(inner.third_party_header(), Some("N0CALL>APRS"));
+    assert_eq!(outer.third_party().unwrap().unwrap().comment().unwrap(), " Hi ");
+
+    let status = Packet::new("N0CALL>APRS:}N1CALL>APRS:>Status").unwrap();
+    match parser.third_party(&status) {
+        Some(Err(Error::NotAccepted(Some(PacketType::Status)))) => {},
+        other => panic!("{:?}", other.map(|r| r.map(|p| p.to_owned()))),
+    }
+    let ax25 = Parser::builder().ax25(true).build();
+    assert!(ax25.third_party(&Packet::new("N0CALL>APRS:>Status").unwrap()).is_none());
+}
@@ -0,0 +1,109 @@
+#[macro_use] extern crate approx;
+extern crate fap;
+
+use fap::{Error, OwnedPacket, PacketBuilder, PacketType, PositionReport, Timestamp, Weather, WeatherReport};
+use std::time::{Duration, UNIX_EPOCH};
+
+const MPH: f64 = 1.609344 / 3.6;
+const HINCH: f64 = 0.254;
+
+fn round_trip(report: &WeatherReport) -> (OwnedPacket, Weather) {
+    let raw = PacketBuilder::new("N0CALL").weather(report).unwrap();
+    let packet = OwnedPacket::new(raw.as_str()).unwrap_or_else(|e| panic!("{}: {}", raw, e));
+    let weather = packet.wx_report.clone().unwrap_or_else(|| panic!("no weather in {}", raw));
+    (packet, weather)
+}
+
+fn full(report: WeatherReport) -> WeatherReport {
+    report.wind(220, 4.0)
+        .gust(9.5)
+        .temperature(-12.3)
+        .rain_1h(1.3)
+        .rain_24h(25.4)
+        .rain_midnight(12.0)
+        .humidity(100)
+        .pressure(1013.25)
+        .luminosity(1234)
+}
+
+fn check_full(weather: &Weather) {
+    assert_eq!(weather.wind_dir, Some(220));
+    assert_abs_diff_eq!(weather.wind_speed.unwrap(), 9.0 * MPH, epsilon = 1e-9);
+    assert_abs_diff_eq!(weather.wind_gust.unwrap(), 21.0 * MPH, epsilon = 1e-9);
+    assert_abs_diff_eq!(weather.temp.unwrap(), (10.0 - 32.0) / 1.8, epsilon = 1e-9);
+    assert_abs_diff_eq!(weather.rain_1h.unwrap(), 5.0 * HINCH, epsilon = 1e-9);
+    assert_abs_diff_eq!(weather.rain_24h.unwrap(), 100.0 * HINCH, epsilon = 1e-9);
+    assert_abs_diff_eq!(weather.rain_midnight.unwrap(), 47.0 * HINCH, epsilon = 1e-9);
+    assert_eq!(weather.humidity, Some(100));
+    assert_abs_diff_eq!(weather.pressure.unwrap(), 1013.3, epsilon = 1e-9);
+    assert_eq!(weather.luminosity, Some(1234));
+}
+
+#[test]
+fn positionless() {
+    let time = UNIX_EPOCH + Duration::from_secs(1528597526);
+    let report = full(WeatherReport::positionless(time)).comment("eMB31");
+    let raw = PacketBuilder::new("N0CALL").weather(&report).unwrap();
+    assert_eq!(raw, "N0CALL>APRS:_06100225c220s009g021t010r005p100P047h00b10133l234eMB31");
+    let (packet, weather) = round_trip(&report);
+    assert_eq!(packet.packet_type, Some(PacketType::Wx));
+    check_full(&weather);
+    assert_eq!(weather.soft.as_deref(), Some("eMB31"));
+
+    let (_, weather) = round_trip(&WeatherReport::positionless(time).wind(0, 0.0).temperature(30.0));
+    assert_eq!(weather.wind_dir, Some(0));
+    assert_eq!(weather.wind_speed, Some(0.0));
+    assert_eq!(weather.wind_gust, None);
+    assert_abs_diff_eq!(weather.temp.unwrap(), 30.0, epsilon = 0.3);
+    assert_eq!(weather.humidity, None);
+}
+
+#[test]
+fn with_position() {
+    let position = PositionReport::new(49.0583, -72.0292).symbol('/', '>');
+    let report = full(WeatherReport::with_position(position.clone()));
+    let raw = PacketBuilder::new("N0CALL").weather(&report).unwrap();
+    assert_eq!(raw, "N0CALL>APRS:!4903.50N/07201.75W_220/009g021t010r005p100P047h00b10133l234");
+    let (packet, weather) = round_trip(&report);
+    assert_eq!(packet.packet_type, Some(PacketType::Location));
+    assert_eq!(packet.symbol_code, Some('_'));
+    assert_abs_diff_eq!(packet.latitude.unwrap(), 49.0583, epsilon = 0.0001);
+    check_full(&weather);
+
+    let time = UNIX_EPOCH + Duration::from_secs(1528597526);
+    let report = WeatherReport::with_position(position.timestamp(Timestamp::Dhm(time)).messaging(true))
+        .temperature(20.0)
+        .luminosity(999)
+        .comment("Home station");
+    let raw = PacketBuilder::new("N0CALL").weather(&report).unwrap();
+    assert_eq!(raw, "N0CALL>APRS:@100225z4903.50N/07201.75W_.../...g...t068L999Home station");
+    let (packet, weather) = round_trip(&report);
+    assert_eq!(packet.comment.as_deref(), Some("Home station"));
+    assert_eq!(weather.wind_dir, None);
+    assert_eq!(weather.luminosity, Some(999));
+}
+
+#[test]
+fn invalid_reports() {
+    let base = WeatherReport::positionless(UNIX_EPOCH);
+    let position = PositionReport::new(49.0583, -72.0292);
+    let rejected = |report: WeatherReport| match PacketBuilder::new("N0CALL").weather(&report) {
+        Err(Error::Encode(_)) => {},
+        other => panic!("{:?} not rejected: {:?}", report, other),
+    };
+    rejected(base.clone().wind(361, 1.0));
+    rejected(base.clone().wind(0, -1.0));
+    rejected(base.clone().gust(500.0));
+    rejected(base.clone().temperature(-80.0));
+    rejected(base.clone().temperature(f64::NAN));
+    rejected(base.clone().rain_1h(300.0));
+    rejected(base.clone().humidity(0));
+    rejected(base.clone().humidity(101));
+    rejected(base.clone().pressure(-1.0));
+    rejected(base.clone().pressure(10000.0));
+    rejected(base.clone().luminosity(2000));
+    rejected(base.clone().comment("a\nb"));
+    rejected(WeatherReport::with_position(position.clone().compressed(true)));
+    rejected(WeatherReport::with_position(position.clone().course_speed(90, 10.0)));
+    rejected(WeatherReport::with_position(position.clone().comment("x")));
+}